@@ -0,0 +1,349 @@
+// reading the cookie databases of Firefox (`cookies.sqlite`, table `moz_cookies`) and Chromium
+// (`Cookies`, table `cookies`) from a browser profile. the database is opened read-only where
+// it is; a browser holds it locked while running, so pass a copy of it, or the database of a
+// browser which has been closed
+
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use rusqlite::{self, Connection, OpenFlags};
+
+use jar::StoredCookie;
+use validate;
+use {CookieBuilder, CookieJar, Priority, SameSite, Timestamp};
+
+// the seconds from 1601-01-01T00:00:00Z, Chromium's epoch, to the unix epoch
+const CHROMIUM_EPOCH_OFFSET_SECS: i64 = 11_644_473_600;
+// Firefox stores expiry in unix seconds, and newer versions in milliseconds; a value past
+// 9999-12-31 is taken to be milliseconds
+const FIREFOX_MAX_EXPIRY_SECS: i64 = 253_402_300_799;
+
+// partitioned cookies are left out, since the jar keys partitions by site rather than by the
+// browsers' partition keys
+const FIREFOX_QUERY: &str = "SELECT host, name, value, path, expiry, isSecure, isHttpOnly, \
+                             sameSite FROM moz_cookies \
+                             WHERE originAttributes NOT LIKE '%partitionKey=%' \
+                             ORDER BY creationTime";
+const CHROMIUM_QUERY: &str = "SELECT host_key, name, value, encrypted_value, path, expires_utc, \
+                              is_secure, is_httponly, samesite, priority FROM cookies \
+                              WHERE top_frame_site_key = '' ORDER BY creation_utc";
+
+#[derive(Debug)]
+pub enum ImportError {
+    Sqlite(rusqlite::Error),
+    // a Chromium cookie whose value is encrypted with a key from the operating system's
+    // keychain, by the host and name it is stored under
+    Encrypted { host: String, name: String },
+    // a cookie with an empty name, or '=' or ';' in its name, or ';' in its value, which would
+    // not serialize as the same cookie; by the host and name it is stored under
+    InvalidPair { host: String, name: String },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImportError::Sqlite(ref e) => e.fmt(f),
+            ImportError::Encrypted { ref host, ref name } => {
+                write!(f, "cookie {} for {} has an encrypted value", name, host)
+            }
+            ImportError::InvalidPair { ref host, ref name } => {
+                write!(f, "cookie {:?} for {} has an invalid name or value", name, host)
+            }
+        }
+    }
+}
+
+impl Error for ImportError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ImportError::Sqlite(ref e) => Some(e),
+            ImportError::Encrypted { .. } | ImportError::InvalidPair { .. } => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for ImportError {
+    fn from(e: rusqlite::Error) -> ImportError {
+        ImportError::Sqlite(e)
+    }
+}
+
+// a row of either database, as the fields of a cookie
+struct Row {
+    host: String,
+    name: String,
+    value: String,
+    path: String,
+    expiry_time: Option<Timestamp>,
+    secure: bool,
+    httponly: bool,
+    same_site: Option<SameSite>,
+    priority: Option<Priority>,
+}
+
+impl Row {
+    fn checked(self) -> Result<Row, ImportError> {
+        if !validate::is_storable_pair(&self.name, &self.value) {
+            return Err(ImportError::InvalidPair {
+                host: self.host,
+                name: self.name,
+            });
+        }
+        Ok(self)
+    }
+
+    // a host with a leading '.' is a Domain cookie; without one, the cookie is host-only
+    fn restore(self, now: Timestamp) -> StoredCookie {
+        let (domain, host_only) = match self.host.strip_prefix('.') {
+            Some(domain) => (domain.to_lowercase(), false),
+            None => (self.host.to_lowercase(), true),
+        };
+        let path = if self.path.starts_with('/') { self.path } else { "/".to_owned() };
+        let mut builder = CookieBuilder::new(self.name, self.value)
+            .path(&path[..])
+            .secure(self.secure)
+            .httponly(self.httponly)
+            .same_site(self.same_site)
+            .priority(self.priority)
+            .expires(self.expiry_time);
+        if !host_only {
+            builder = builder.domain(&domain[..]);
+        }
+        let cookie = builder.build();
+        StoredCookie::restored(cookie, domain, path, host_only, self.expiry_time, now)
+    }
+}
+
+impl CookieJar {
+    // adds the cookies of a Firefox `cookies.sqlite` database, replacing those with the same
+    // name, domain and path
+    pub fn import_firefox<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ImportError> {
+        let conn = open(path.as_ref())?;
+        self.import_firefox_at(&conn, Timestamp::now())
+    }
+
+    // adds the cookies of a Chromium `Cookies` database, replacing those with the same name,
+    // domain and path. nothing is added if any value is encrypted, as they are unless the
+    // browser was configured to store them in plain text
+    pub fn import_chromium<P: AsRef<Path>>(&mut self, path: P) -> Result<(), ImportError> {
+        let conn = open(path.as_ref())?;
+        self.import_chromium_at(&conn, Timestamp::now())
+    }
+
+    fn import_firefox_at(&mut self, conn: &Connection, now: Timestamp) -> Result<(), ImportError> {
+        let mut stmt = conn.prepare(FIREFOX_QUERY)?;
+        let mut rows = stmt.query([])?;
+        let mut cookies = Vec::new();
+        while let Some(row) = rows.next()? {
+            let expiry: i64 = row.get(4)?;
+            let expiry = if expiry > FIREFOX_MAX_EXPIRY_SECS { expiry / 1000 } else { expiry };
+            cookies.push(Row {
+                host: row.get(0)?,
+                name: row.get(1)?,
+                value: row.get(2)?,
+                path: row.get(3)?,
+                expiry_time: Some(Timestamp::from_unix(expiry)),
+                secure: row.get(5)?,
+                httponly: row.get(6)?,
+                same_site: match row.get(7)? {
+                    0 => Some(SameSite::None),
+                    1 => Some(SameSite::Lax),
+                    2 => Some(SameSite::Strict),
+                    _ => None,
+                },
+                priority: None,
+            }
+            .checked()?);
+        }
+        self.insert_rows(cookies, now);
+        Ok(())
+    }
+
+    fn import_chromium_at(&mut self,
+                          conn: &Connection,
+                          now: Timestamp)
+                          -> Result<(), ImportError> {
+        let mut stmt = conn.prepare(CHROMIUM_QUERY)?;
+        let mut rows = stmt.query([])?;
+        let mut cookies = Vec::new();
+        while let Some(row) = rows.next()? {
+            let host: String = row.get(0)?;
+            let name: String = row.get(1)?;
+            let encrypted: Vec<u8> = row.get(3)?;
+            if !encrypted.is_empty() {
+                return Err(ImportError::Encrypted { host, name });
+            }
+            // microseconds since 1601, or 0 for a session cookie
+            let expiry_time = match row.get::<_, i64>(5)? {
+                0 => None,
+                us => Some(Timestamp::from_unix(us.div_euclid(1_000_000) -
+                                                CHROMIUM_EPOCH_OFFSET_SECS)),
+            };
+            cookies.push(Row {
+                host,
+                name,
+                value: row.get(2)?,
+                path: row.get(4)?,
+                expiry_time,
+                secure: row.get(6)?,
+                httponly: row.get(7)?,
+                // -1 is unspecified
+                same_site: match row.get(8)? {
+                    0 => Some(SameSite::None),
+                    1 => Some(SameSite::Lax),
+                    2 => Some(SameSite::Strict),
+                    _ => None,
+                },
+                // Medium, the default, is stored for cookies which set no Priority
+                priority: match row.get(9)? {
+                    0 => Some(Priority::Low),
+                    2 => Some(Priority::High),
+                    _ => None,
+                },
+            }
+            .checked()?);
+        }
+        self.insert_rows(cookies, now);
+        Ok(())
+    }
+
+    fn insert_rows(&mut self, rows: Vec<Row>, now: Timestamp) {
+        for row in rows {
+            self.insert_at(row.restore(now), now);
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<Connection, rusqlite::Error> {
+    Connection::open_with_flags(path,
+                                OpenFlags::SQLITE_OPEN_READ_ONLY |
+                                OpenFlags::SQLITE_OPEN_NO_MUTEX)
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+    use url::Url;
+
+    use super::ImportError;
+    use {CookieJar, Priority, SameSite, Timestamp};
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn firefox() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE moz_cookies (id INTEGER PRIMARY KEY, \
+                            originAttributes TEXT NOT NULL DEFAULT '', name TEXT, value TEXT, \
+                            host TEXT, path TEXT, expiry INTEGER, lastAccessed INTEGER, \
+                            creationTime INTEGER, isSecure INTEGER, isHttpOnly INTEGER, \
+                            sameSite INTEGER);
+                            INSERT INTO moz_cookies VALUES
+                              (1, '', 'sid', 'abc', 'www.example.com', '/account',
+                               2000000000, 0, 2, 1, 1, 2),
+                              (2, '', 'theme', 'dark', '.example.com', '/',
+                               2000000000000, 0, 1, 0, 0, 256),
+                              (3, '', 'old', '1', 'example.com', '/', 1000, 0, 3, 0, 0, 0),
+                              (4, '^partitionKey=%28https%2Cexample.org%29', 'chip', '1',
+                               'example.com', '/', 2000000000, 0, 4, 1, 0, 0);")
+            .unwrap();
+
+        let mut jar = CookieJar::new();
+        jar.import_firefox_at(&conn, Timestamp::from_unix(1_500_000_000)).unwrap();
+        assert_eq!(jar.len(), 2);
+
+        let sid = jar.iter().find(|c| c.cookie().name() == "sid").unwrap();
+        assert!(sid.host_only());
+        assert_eq!(sid.domain(), "www.example.com");
+        assert_eq!(sid.expiry_time(), Some(Timestamp::from_unix(2_000_000_000)));
+        assert_eq!(sid.cookie().as_str(),
+                   "sid=abc; Path=/account; Secure; HttpOnly; SameSite=Strict; \
+                    Expires=Wed, 18 May 2033 03:33:20 GMT");
+
+        // expiry in milliseconds
+        let theme = jar.iter().find(|c| c.cookie().name() == "theme").unwrap();
+        assert!(!theme.host_only());
+        assert_eq!(theme.domain(), "example.com");
+        assert_eq!(theme.expiry_time(), Some(Timestamp::from_unix(2_000_000_000)));
+        assert_eq!(theme.cookie().same_site(), None);
+
+        assert_eq!(jar.cookies_for_at(&url("https://www.example.com/account"),
+                                      &url("https://www.example.com/"),
+                                      Timestamp::from_unix(1_500_000_000))
+                       .iter()
+                       .map(|c| c.name())
+                       .collect::<Vec<_>>(),
+                   vec!["sid", "theme"]);
+    }
+
+    fn chromium_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE cookies (creation_utc INTEGER NOT NULL, \
+                            host_key TEXT NOT NULL, top_frame_site_key TEXT NOT NULL, \
+                            name TEXT NOT NULL, value TEXT NOT NULL, \
+                            encrypted_value BLOB NOT NULL, path TEXT NOT NULL, \
+                            expires_utc INTEGER NOT NULL, is_secure INTEGER NOT NULL, \
+                            is_httponly INTEGER NOT NULL, samesite INTEGER NOT NULL, \
+                            priority INTEGER NOT NULL);
+                            INSERT INTO cookies VALUES
+                              (1, 'www.example.com', '', 'sid', 'abc', X'', '/',
+                               13323225600000000, 1, 1, 1, 2),
+                              (2, '.example.com', '', 'theme', 'dark', X'', '/',
+                               0, 0, 0, -1, 1),
+                              (3, 'example.com', 'https://example.org', 'chip', '1', X'',
+                               '/', 0, 1, 0, 0, 1);")
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn chromium() {
+        let conn = chromium_db();
+        let mut jar = CookieJar::new();
+        jar.import_chromium_at(&conn, Timestamp::from_unix(1_500_000_000)).unwrap();
+        assert_eq!(jar.len(), 2);
+
+        // 2023-03-14T00:00:00Z
+        let sid = jar.iter().find(|c| c.cookie().name() == "sid").unwrap();
+        assert!(sid.host_only());
+        assert_eq!(sid.expiry_time(), Some(Timestamp::from_unix(1_678_752_000)));
+        assert_eq!(sid.priority(), Priority::High);
+        assert_eq!(sid.cookie().same_site(), Some(SameSite::Lax));
+
+        let theme = jar.iter().find(|c| c.cookie().name() == "theme").unwrap();
+        assert!(!theme.host_only());
+        assert!(!theme.persistent());
+        assert_eq!(theme.cookie().as_str(),
+                   "theme=dark; Domain=example.com; Path=/");
+
+        conn.execute_batch("INSERT INTO cookies VALUES (4, '.example.com', '', 'enc', '', \
+                            X'7631300102', '/', 0, 0, 0, -1, 1);")
+            .unwrap();
+        let mut jar = CookieJar::new();
+        let e = jar.import_chromium_at(&conn, Timestamp::from_unix(1_500_000_000)).unwrap_err();
+        match e {
+            ImportError::Encrypted { ref host, ref name } => {
+                assert_eq!((&host[..], &name[..]), (".example.com", "enc"));
+            }
+            _ => panic!("expected an encrypted value"),
+        }
+        assert_eq!(e.to_string(), "cookie enc for .example.com has an encrypted value");
+        assert!(jar.is_empty());
+
+        conn.execute_batch("DELETE FROM cookies WHERE name = 'enc';
+                            INSERT INTO cookies VALUES (5, 'example.com', '', 'split', 'a;b', \
+                            X'', '/', 0, 0, 0, -1, 1);")
+            .unwrap();
+        let e = jar.import_chromium_at(&conn, Timestamp::from_unix(1_500_000_000)).unwrap_err();
+        assert!(matches!(e, ImportError::InvalidPair { ref host, ref name }
+                         if host == "example.com" && name == "split"));
+        assert_eq!(e.to_string(), "cookie \"split\" for example.com has an invalid name or value");
+        assert!(jar.is_empty());
+
+        assert!(matches!(jar.import_chromium("/nonexistent/Cookies"),
+                         Err(ImportError::Sqlite(_))));
+    }
+}
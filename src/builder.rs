@@ -0,0 +1,368 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use parse;
+use validate;
+use {normalize_expires, Cookie, Priority, SameSite, Timestamp};
+use {DOMAIN_PREFIX, EXPIRES_PREFIX, HTTPONLY_FLAG, MAX_AGE_PREFIX, PARTITIONED_FLAG, PATH_PREFIX,
+     PRIORITY_PREFIX, SAME_SITE_PREFIX, SECURE_FLAG};
+
+// an Expires value is always a four-digit-year IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+const IMF_FIXDATE_LEN: usize = 29;
+
+// collects the fields of a cookie and renders its serialization once, into a string allocated
+// at exactly the final length, rather than moving the tail of the string on every setter call.
+// the fields are normalized as the corresponding `Cookie` setters would: surrounding
+// whitespace is trimmed, an empty Domain or Path is no Domain or Path, and Expires is whole
+// seconds. borrowed strings are only copied once, into the serialization
+#[derive(Debug, Clone)]
+pub struct CookieBuilder<'a> {
+    name: Cow<'a, str>,
+    value: Cow<'a, str>,
+    domain: Option<Cow<'a, str>>,
+    path: Option<Cow<'a, str>>,
+    max_age: Option<i64>,
+    extensions: Vec<(Cow<'a, str>, Option<Cow<'a, str>>)>,
+    secure: bool,
+    httponly: bool,
+    partitioned: bool,
+    same_site: Option<SameSite>,
+    priority: Option<Priority>,
+    expires: Option<Timestamp>,
+}
+
+impl<'a> CookieBuilder<'a> {
+    pub fn new<N, V>(name: N, value: V) -> CookieBuilder<'a>
+        where N: Into<Cow<'a, str>>,
+              V: Into<Cow<'a, str>>
+    {
+        CookieBuilder {
+            name: name.into(),
+            value: value.into(),
+            domain: None,
+            path: None,
+            max_age: None,
+            extensions: Vec::new(),
+            secure: false,
+            httponly: false,
+            partitioned: false,
+            same_site: None,
+            priority: None,
+            expires: None,
+        }
+    }
+
+    pub fn name<N: Into<Cow<'a, str>>>(mut self, name: N) -> CookieBuilder<'a> {
+        self.name = name.into();
+        self
+    }
+
+    pub fn value<V: Into<Cow<'a, str>>>(mut self, value: V) -> CookieBuilder<'a> {
+        self.value = value.into();
+        self
+    }
+
+    pub fn domain<D: Into<Cow<'a, str>>>(mut self, domain: D) -> CookieBuilder<'a> {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    pub fn path<P: Into<Cow<'a, str>>>(mut self, path: P) -> CookieBuilder<'a> {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Option<i64>) -> CookieBuilder<'a> {
+        self.max_age = max_age;
+        self
+    }
+
+    // as `Cookie::set_extension`: replaces an extension of the same name, compared
+    // case-insensitively, in place. `build` leaves out extensions which would not parse back
+    // as the same attribute, such as a name containing '=' or a value containing ';'
+    pub fn extension<N, V>(mut self, name: N, value: Option<V>) -> CookieBuilder<'a>
+        where N: Into<Cow<'a, str>>,
+              V: Into<Cow<'a, str>>
+    {
+        let name = name.into();
+        let value = value.map(Into::into);
+        let existing = self.extensions
+            .iter()
+            .position(|(n, _)| n.trim().eq_ignore_ascii_case(name.trim()));
+        match existing {
+            Some(i) => self.extensions[i] = (name, value),
+            None => self.extensions.push((name, value)),
+        }
+        self
+    }
+
+    pub fn remove_extension(mut self, name: &str) -> CookieBuilder<'a> {
+        self.extensions.retain(|(n, _)| !n.trim().eq_ignore_ascii_case(name.trim()));
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> CookieBuilder<'a> {
+        self.secure = secure;
+        self
+    }
+
+    pub fn httponly(mut self, httponly: bool) -> CookieBuilder<'a> {
+        self.httponly = httponly;
+        self
+    }
+
+    pub fn partitioned(mut self, partitioned: bool) -> CookieBuilder<'a> {
+        self.partitioned = partitioned;
+        self
+    }
+
+    pub fn same_site(mut self, same_site: Option<SameSite>) -> CookieBuilder<'a> {
+        self.same_site = same_site;
+        self
+    }
+
+    pub fn priority(mut self, priority: Option<Priority>) -> CookieBuilder<'a> {
+        self.priority = priority;
+        self
+    }
+
+    pub fn expires(mut self, expires: Option<Timestamp>) -> CookieBuilder<'a> {
+        self.expires = expires;
+        self
+    }
+
+    pub fn build(self) -> Cookie {
+        let name = self.name.trim();
+        let value = self.value.trim();
+        let domain = self.domain.as_ref().map(|d| d.trim()).filter(|d| !d.is_empty());
+        let path = self.path.as_ref().map(|p| p.trim()).filter(|p| !p.is_empty());
+        let extensions = self.extensions
+            .iter()
+            .map(|(n, v)| (n.trim(), v.as_ref().map(|v| v.trim())))
+            .filter(|&(n, v)| {
+                !parse::is_known_attribute(n) && validate::check_extension(n, v).is_ok()
+            });
+        let expires = self.expires.map(normalize_expires);
+
+        let extensions_len: usize = extensions.clone()
+            .map(|(n, v)| 2 + n.len() + v.map_or(0, |v| 1 + v.len()))
+            .sum();
+        let len = name.len() + 1 + value.len() +
+                  domain.map_or(0, |d| DOMAIN_PREFIX.len() + d.len()) +
+                  path.map_or(0, |p| PATH_PREFIX.len() + p.len()) +
+                  self.max_age.map_or(0, |m| MAX_AGE_PREFIX.len() + decimal_len(m)) +
+                  extensions_len + if self.secure { SECURE_FLAG.len() } else { 0 } +
+                  if self.httponly { HTTPONLY_FLAG.len() } else { 0 } +
+                  if self.partitioned { PARTITIONED_FLAG.len() } else { 0 } +
+                  self.same_site.map_or(0, |s| SAME_SITE_PREFIX.len() + s.as_str().len()) +
+                  self.priority.map_or(0, |p| PRIORITY_PREFIX.len() + p.as_str().len()) +
+                  expires.map_or(0, |_| EXPIRES_PREFIX.len() + IMF_FIXDATE_LEN);
+
+        let mut s = String::with_capacity(len);
+        s.push_str(name);
+        let name_end = s.len();
+        s.push('=');
+        s.push_str(value);
+        let value_end = s.len();
+        let domain_end = domain.map(|d| {
+            s.push_str(DOMAIN_PREFIX);
+            s.push_str(d);
+            s.len()
+        });
+        let path_end = path.map(|p| {
+            s.push_str(PATH_PREFIX);
+            s.push_str(p);
+            s.len()
+        });
+        let max_age = self.max_age.map(|m| {
+            s.push_str(MAX_AGE_PREFIX);
+            write!(s, "{}", m).unwrap();
+            (m, s.len())
+        });
+        for (n, v) in extensions {
+            s.push_str("; ");
+            s.push_str(n);
+            if let Some(v) = v {
+                s.push('=');
+                s.push_str(v);
+            }
+        }
+        for &(set, flag) in &[(self.secure, SECURE_FLAG),
+                              (self.httponly, HTTPONLY_FLAG),
+                              (self.partitioned, PARTITIONED_FLAG)] {
+            if set {
+                s.push_str(flag);
+            }
+        }
+        if let Some(same_site) = self.same_site {
+            s.push_str(SAME_SITE_PREFIX);
+            s.push_str(same_site.as_str());
+        }
+        if let Some(priority) = self.priority {
+            s.push_str(PRIORITY_PREFIX);
+            s.push_str(priority.as_str());
+        }
+        if let Some(expires) = expires {
+            s.push_str(EXPIRES_PREFIX);
+            write!(s, "{}", expires).unwrap();
+        }
+        debug_assert_eq!(s.len(), len);
+
+        Cookie {
+            serialization: s,
+            name_end,
+            value_end,
+            domain_end,
+            path_end,
+            max_age,
+            extensions_len,
+            secure: self.secure,
+            httponly: self.httponly,
+            partitioned: self.partitioned,
+            same_site: self.same_site,
+            priority: self.priority,
+            expires,
+        }
+    }
+}
+
+impl Cookie {
+    // a builder holding this cookie's fields, for making several edits with a single render
+    pub fn into_builder(self) -> CookieBuilder<'static> {
+        CookieBuilder {
+            name: Cow::Owned(self.name().to_owned()),
+            value: Cow::Owned(self.value().to_owned()),
+            domain: self.domain().map(|d| Cow::Owned(d.to_owned())),
+            path: self.path().map(|p| Cow::Owned(p.to_owned())),
+            max_age: self.max_age(),
+            extensions: self.extensions()
+                .map(|(n, v)| (Cow::Owned(n.to_owned()), v.map(|v| Cow::Owned(v.to_owned()))))
+                .collect(),
+            secure: self.secure,
+            httponly: self.httponly,
+            partitioned: self.partitioned,
+            same_site: self.same_site,
+            priority: self.priority,
+            expires: self.expires,
+        }
+    }
+}
+
+fn decimal_len(n: i64) -> usize {
+    let mut len = if n < 0 { 2 } else { 1 };
+    let mut n = n.unsigned_abs();
+    while n >= 10 {
+        n /= 10;
+        len += 1;
+    }
+    len
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use super::{decimal_len, CookieBuilder};
+    use {Cookie, Priority, SameSite, Timestamp};
+
+    #[test]
+    fn build() {
+        let expires = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let built = CookieBuilder::new("sid", "abc")
+            .domain("example.com")
+            .path("/")
+            .max_age(Some(3600))
+            .extension("SameParty", None::<&str>)
+            .secure(true)
+            .httponly(true)
+            .partitioned(true)
+            .same_site(Some(SameSite::None))
+            .priority(Some(Priority::High))
+            .expires(Some(expires))
+            .build();
+        assert_eq!(built.as_str(),
+                   "sid=abc; Domain=example.com; Path=/; Max-Age=3600; SameParty; Secure; \
+                    HttpOnly; Partitioned; SameSite=None; Priority=High; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        assert_eq!(built.serialization.capacity(), built.as_str().len());
+
+        let mut set = Cookie::new("sid", "abc");
+        set.set_expires(Some(expires))
+            .set_priority(Some(Priority::High))
+            .set_same_site(Some(SameSite::None))
+            .set_partitioned(true)
+            .set_httponly(true)
+            .set_secure(true)
+            .set_extension("SameParty", None)
+            .set_max_age(3600)
+            .set_path("/")
+            .set_domain("example.com");
+        assert_eq!(built.as_str(), set.as_str());
+        assert_eq!(built, set);
+
+        // the offsets are those the setters would have kept
+        let mut built = built;
+        set.set_domain("").set_extension("X", Some("1")).set_expires(None).set_max_age(-1);
+        built.set_domain("").set_extension("X", Some("1")).set_expires(None).set_max_age(-1);
+        assert_eq!(built.as_str(), set.as_str());
+        assert_eq!(built.expires_str(), None);
+        assert_eq!(built.max_age_str(), Some("-1"));
+    }
+
+    #[test]
+    fn normalization() {
+        let c = CookieBuilder::new(" foo ", String::from(" bar "))
+            .domain(" ")
+            .path(String::new())
+            .extension(" X-Tier ", Some(" Low "))
+            .extension("x-tier", Some("High"))
+            .extension("Secure", None::<&str>)
+            .extension(" ", Some("1"))
+            .extension("X=Y", Some("z"))
+            .extension("X-Inject", Some("1; Domain=evil.com"))
+            .extension("X-Ctl", Some("a\nb"))
+            .max_age(Some(i64::MIN))
+            .expires(Some(Timestamp::from_unix(i64::MAX)))
+            .build();
+        assert_eq!(c.as_str(),
+                   "foo=bar; Max-Age=-9223372036854775808; x-tier=High; \
+                    Expires=Fri, 31 Dec 9999 23:59:59 GMT");
+        assert_eq!(c.secure(), false);
+        assert_eq!(c.domain(), None);
+        assert_eq!(c.path(), None);
+        assert_eq!(c.serialization.capacity(), c.as_str().len());
+
+        let name = String::from("X-Trace");
+        let c = CookieBuilder::new("a", "b")
+            .extension(name.clone(), Some(format!("{}-1", "span")))
+            .extension(name, None::<String>)
+            .build();
+        assert_eq!(c.as_str(), "a=b; X-Trace");
+
+        let c = CookieBuilder::new("a", "b").build();
+        assert_eq!(c.as_str(), "a=b");
+        assert_eq!(c, Cookie::new("a", "b"));
+    }
+
+    #[test]
+    fn into_builder() {
+        let c = Cookie::parse("sid=abc; Path=/; SameParty; X-Trace=1; SameParty; Secure; \
+                               Max-Age=60")
+            .unwrap();
+        let edited = c.clone()
+            .into_builder()
+            .value("def")
+            .max_age(None)
+            .httponly(true)
+            .remove_extension("x-trace")
+            .build();
+        assert_eq!(edited.as_str(), "sid=def; Path=/; SameParty; SameParty; Secure; HttpOnly");
+        assert_eq!(c.clone().into_builder().build(), c);
+    }
+
+    #[test]
+    fn decimal_lengths() {
+        for &n in &[0, 9, 10, -1, -10, 99_999, i64::MAX, i64::MIN] {
+            assert_eq!(decimal_len(n), n.to_string().len());
+        }
+    }
+}
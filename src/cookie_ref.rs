@@ -0,0 +1,189 @@
+use std::ops::Range;
+
+use parse;
+use {Cookie, Extensions, ParseError, Priority, RangeArg, SameSite, Timestamp};
+
+// a borrowed view of a Set-Cookie header; attributes are located by index into `source`,
+// in whatever order and casing the sender used
+#[derive(Clone)]
+pub struct CookieRef<'a> {
+    pub(crate) source: &'a str,
+    pub(crate) name: Range<usize>,
+    pub(crate) value: Range<usize>,
+    pub(crate) domain: Option<Range<usize>>,
+    pub(crate) path: Option<Range<usize>>,
+    pub(crate) max_age: Option<(i64, Range<usize>)>,
+    pub(crate) secure: bool,
+    pub(crate) httponly: bool,
+    pub(crate) partitioned: bool,
+    pub(crate) same_site: Option<SameSite>,
+    pub(crate) priority: Option<Priority>,
+    pub(crate) expires: Option<(Timestamp, Range<usize>)>,
+}
+
+impl<'a> CookieRef<'a> {
+    pub fn parse(s: &'a str) -> Result<CookieRef<'a>, ParseError> {
+        parse::parse(s)
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.source
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name.slice_of(self.source)
+    }
+
+    pub fn value(&self) -> &'a str {
+        self.value.slice_of(self.source)
+    }
+
+    pub fn cookie_pair(&self) -> (&'a str, &'a str) {
+        (self.name(), self.value())
+    }
+
+    pub fn domain(&self) -> Option<&'a str> {
+        self.domain.as_ref().map(|r| r.slice_of(self.source))
+    }
+
+    pub fn path(&self) -> Option<&'a str> {
+        self.path.as_ref().map(|r| r.slice_of(self.source))
+    }
+
+    pub fn max_age(&self) -> Option<i64> {
+        self.max_age.as_ref().map(|&(delta, _)| delta)
+    }
+
+    pub fn max_age_str(&self) -> Option<&'a str> {
+        self.max_age.as_ref().map(|(_, r)| r.slice_of(self.source))
+    }
+
+    // rescans the source rather than keeping a list, so parsing stays allocation-free
+    pub fn extensions(&self) -> Extensions<'a> {
+        Extensions::new(self.source)
+    }
+
+    pub fn secure(&self) -> bool {
+        self.secure
+    }
+
+    pub fn httponly(&self) -> bool {
+        self.httponly
+    }
+
+    pub fn partitioned(&self) -> bool {
+        self.partitioned
+    }
+
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    pub fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
+
+    pub fn expires(&self) -> Option<Timestamp> {
+        self.expires.as_ref().map(|&(tm, _)| tm)
+    }
+
+    pub fn expires_str(&self) -> Option<&'a str> {
+        self.expires.as_ref().map(|(_, r)| r.slice_of(self.source))
+    }
+
+    pub fn to_owned(&self) -> Cookie {
+        let mut cookie = Cookie::new(self.name(), self.value());
+        if let Some(domain) = self.domain() {
+            cookie.set_domain(domain);
+        }
+        if let Some(path) = self.path() {
+            cookie.set_path(path);
+        }
+        if let Some(max_age) = self.max_age() {
+            cookie.set_max_age(max_age);
+        }
+        for (name, value) in self.extensions() {
+            cookie.push_extension(name, value);
+        }
+        cookie.set_secure(self.secure);
+        cookie.set_httponly(self.httponly);
+        cookie.set_partitioned(self.partitioned);
+        cookie.set_same_site(self.same_site);
+        cookie.set_priority(self.priority);
+        cookie.set_expires(self.expires());
+        cookie
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use super::CookieRef;
+    use Timestamp;
+
+    #[test]
+    fn borrowed_fields() {
+        let expires = "Thu, 22 Mar 2012 14:53:18 GMT";
+        let tm = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+
+        let header = String::from("foo = bar; secure; Path=/foo ;max-age=60; \
+                                   expires=Thu, 22 Mar 2012 14:53:18 GMT; Domain=.example.com");
+        let c = CookieRef::parse(&header).unwrap();
+        assert_eq!(c.as_str(), &header[..]);
+        assert_eq!(c.cookie_pair(), ("foo", "bar"));
+        assert_eq!(c.domain(), Some("example.com"));
+        assert_eq!(c.path(), Some("/foo"));
+        assert_eq!(c.max_age(), Some(60));
+        assert_eq!(c.max_age_str(), Some("60"));
+        assert_eq!(c.secure(), true);
+        assert_eq!(c.httponly(), false);
+        assert_eq!(c.expires(), Some(tm));
+        assert_eq!(c.expires_str(), Some(expires));
+
+        // slices point into the original buffer
+        let start = header.as_ptr() as usize;
+        assert_eq!(c.name().as_ptr() as usize - start, 0);
+        assert_eq!(c.value().as_ptr() as usize - start, 6);
+        assert_eq!(c.domain().unwrap().as_ptr() as usize - start, header.len() - 11);
+
+        let owned = c.to_owned();
+        assert_eq!(owned.as_str(),
+                   "foo=bar; Domain=example.com; Path=/foo; Max-Age=60; Secure; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+    }
+
+    #[test]
+    fn non_positive_max_age() {
+        let c = CookieRef::parse("foo=bar; Max-Age=-5").unwrap();
+        assert_eq!(c.max_age(), Some(-5));
+        assert_eq!(c.max_age_str(), Some("-5"));
+        assert_eq!(c.expires(), None);
+        assert_eq!(c.to_owned().as_str(), "foo=bar; Max-Age=-5");
+    }
+
+    #[test]
+    fn empty_domain() {
+        let headers = ["foo=bar; Domain=.", "foo=bar; Domain=", "foo=bar; Domain=a.com; Domain=."];
+        for header in &headers {
+            let c = CookieRef::parse(header).unwrap();
+            assert_eq!(c.domain(), c.to_owned().domain());
+        }
+        assert_eq!(CookieRef::parse("foo=bar; Domain=.").unwrap().domain(), None);
+        assert_eq!(CookieRef::parse("foo=bar; Domain=a.com; Domain=.").unwrap().domain(),
+                   Some("a.com"));
+    }
+
+    #[test]
+    fn extensions() {
+        let header = "id=1; SameParty; X-Tier = High; secure; X-Vendor=a=b; ; Max-Age=60; \
+                      SameParty";
+        let c = CookieRef::parse(header).unwrap();
+        assert_eq!(c.extensions().collect::<Vec<_>>(),
+                   vec![("SameParty", None),
+                        ("X-Tier", Some("High")),
+                        ("X-Vendor", Some("a=b")),
+                        ("SameParty", None)]);
+        assert_eq!(c.to_owned().as_str(),
+                   "id=1; Max-Age=60; SameParty; X-Tier=High; X-Vendor=a=b; SameParty; Secure");
+    }
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::fmt;
+
+use base64::alphabet::URL_SAFE;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use percent_encoding::{percent_decode, percent_encode, AsciiSet, CONTROLS};
+
+// everything outside cookie-octet, plus '%' itself so that decoding is lossless
+const VALUE_ENCODE_SET: &AsciiSet = &CONTROLS.add(b' ')
+    .add(b'"')
+    .add(b',')
+    .add(b';')
+    .add(b'\\')
+    .add(b'%');
+
+// emits unpadded base64url but accepts padding, which is also made of cookie-octets
+const BASE64_URL: GeneralPurpose =
+    GeneralPurpose::new(&URL_SAFE,
+                        GeneralPurposeConfig::new()
+                            .with_encode_padding(false)
+                            .with_decode_padding_mode(DecodePaddingMode::Indifferent));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Percent,
+    Base64Url,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidBase64,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::InvalidBase64 => write!(f, "cookie value is not valid base64url"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+pub fn encode(value: &[u8], encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Percent => percent_encode(value, VALUE_ENCODE_SET).to_string(),
+        Encoding::Base64Url => BASE64_URL.encode(value),
+    }
+}
+
+pub fn decode(value: &str, encoding: Encoding) -> Result<Vec<u8>, DecodeError> {
+    match encoding {
+        Encoding::Percent => Ok(percent_decode(value.as_bytes()).collect()),
+        Encoding::Base64Url => BASE64_URL.decode(value).map_err(|_| DecodeError::InvalidBase64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecodeError, Encoding};
+    use Cookie;
+
+    #[test]
+    fn percent() {
+        let json = "{\"user\": \"zoë\", \"tags\": [\"a;b\", \"100%\"]}";
+        let c = Cookie::new_encoded("data", json, Encoding::Percent);
+        assert_eq!(c.value(),
+                   "{%22user%22:%20%22zo%C3%AB%22%2C%20%22tags%22:%20[%22a%3Bb%22%2C%20%22100%25%22]}");
+        assert!(c.validate().is_ok());
+        assert_eq!(c.value_decoded(Encoding::Percent).unwrap(), json.as_bytes());
+
+        // leading and trailing whitespace survives, rather than being trimmed by set_value
+        let mut c = Cookie::new("foo", "bar");
+        c.set_domain("example.com");
+        c.set_value_encoded(" \t\r\n", Encoding::Percent);
+        assert_eq!(c.as_str(), "foo=%20%09%0D%0A; Domain=example.com");
+        assert_eq!(c.value_decoded(Encoding::Percent).unwrap(), b" \t\r\n");
+
+        // values which were never encoded decode to themselves
+        let c = Cookie::new("foo", "plain-value");
+        assert_eq!(c.value_decoded(Encoding::Percent).unwrap(), b"plain-value");
+    }
+
+    #[test]
+    fn base64_url() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let c = Cookie::new_encoded("bin", &bytes, Encoding::Base64Url);
+        assert!(c.validate().is_ok());
+        assert!(!c.value().contains('='));
+        assert_eq!(c.value_decoded(Encoding::Base64Url).unwrap(), bytes);
+
+        let mut c = Cookie::new("foo", "");
+        c.set_value_encoded([0xfb, 0xff], Encoding::Base64Url);
+        assert_eq!(c.value(), "-_8");
+        c.set_value("-_8=");
+        assert_eq!(c.value_decoded(Encoding::Base64Url).unwrap(), vec![0xfb, 0xff]);
+        c.set_value("not base64!");
+        assert_eq!(c.value_decoded(Encoding::Base64Url), Err(DecodeError::InvalidBase64));
+    }
+}
@@ -0,0 +1,455 @@
+// the cookie objects of HAR 1.2 (HTTP Archive), as browsers' developer tools and proxies
+// export them, and enough of the log around them to collect every cookie it recorded. a HAR
+// file is JSON, so these are read with serde_json or any other JSON deserializer
+
+use std::error::Error;
+use std::fmt;
+
+use url::{self, Url};
+
+use validate;
+use {Cookie, CookieBuilder, CookieJar, SameSite, StoreError, Timestamp};
+
+// a HAR `cookie` object. `expires` is written as an ISO 8601 date-time, and read as one or as
+// a cookie-date, as some tools write it; one which is neither is read as no expiry, and kept
+// in `invalid_expires`. the optional flags default to false when left out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase", from = "HarCookieFields")]
+pub struct HarCookie {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "iso8601::serialize")]
+    pub expires: Option<Timestamp>,
+    pub http_only: bool,
+    pub secure: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<SameSite>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    // the `expires` string as read, when it is not a date. it is not written
+    #[serde(skip)]
+    pub invalid_expires: Option<String>,
+}
+
+// a `cookie` object as read, before its `expires` is parsed
+#[derive(Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "camelCase")]
+struct HarCookieFields {
+    name: String,
+    value: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    domain: Option<String>,
+    #[serde(default)]
+    expires: Option<String>,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    same_site: Option<SameSite>,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+impl From<HarCookieFields> for HarCookie {
+    fn from(fields: HarCookieFields) -> HarCookie {
+        // an empty string is taken for no expiry rather than an invalid one
+        let expires = fields.expires.as_ref().and_then(|s| iso8601::parse(s));
+        let invalid_expires = match expires {
+            Some(_) => None,
+            None => fields.expires.filter(|s| !s.is_empty()),
+        };
+        HarCookie {
+            name: fields.name,
+            value: fields.value,
+            path: fields.path,
+            domain: fields.domain,
+            expires,
+            http_only: fields.http_only,
+            secure: fields.secure,
+            same_site: fields.same_site,
+            comment: fields.comment,
+            invalid_expires,
+        }
+    }
+}
+
+// why a cookie of a HAR log was not stored
+#[derive(Debug)]
+pub enum HarError {
+    // the url of the entry's request does not parse
+    InvalidUrl(url::ParseError),
+    // `expires` is neither an ISO 8601 date-time nor a cookie-date
+    InvalidExpires(String),
+    // the name is empty or contains '=' or ';', or the value contains ';'
+    InvalidPair,
+    // the jar would not accept the cookie from the entry's url
+    Store(StoreError),
+}
+
+impl fmt::Display for HarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HarError::InvalidUrl(ref e) => write!(f, "request url is invalid: {}", e),
+            HarError::InvalidExpires(ref s) => write!(f, "expires {:?} is not a date", s),
+            HarError::InvalidPair => write!(f, "name or value contains invalid characters"),
+            HarError::Store(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for HarError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            HarError::InvalidUrl(ref e) => Some(e),
+            HarError::Store(ref e) => Some(e),
+            HarError::InvalidExpires(_) | HarError::InvalidPair => None,
+        }
+    }
+}
+
+// a cookie of a HAR log which was not stored, by the 0-based index of its entry and its name
+#[derive(Debug)]
+pub struct HarRejection {
+    pub entry: usize,
+    pub name: String,
+    pub error: HarError,
+}
+
+impl fmt::Display for HarRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "entry {}, cookie {:?}: {}", self.entry, self.name, self.error)
+    }
+}
+
+// the top-level object of a HAR file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub struct HarLog {
+    pub entries: Vec<HarEntry>,
+}
+
+// a request and its response
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub struct HarEntry {
+    pub request: HarRequest,
+    pub response: HarResponse,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub struct HarRequest {
+    pub url: String,
+    #[serde(default)]
+    pub cookies: Vec<HarCookie>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "serde_crate")]
+pub struct HarResponse {
+    #[serde(default)]
+    pub cookies: Vec<HarCookie>,
+}
+
+impl HarCookie {
+    // the HAR cookie for a Set-Cookie cookie received at `now`. a Max-Age is written as the
+    // time it runs out at, since HAR has no field for it
+    pub fn from_cookie(cookie: &Cookie, now: Timestamp) -> HarCookie {
+        HarCookie {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            path: cookie.path().map(|p| p.to_owned()),
+            domain: cookie.domain().map(|d| d.to_owned()),
+            expires: cookie.expiry_at(now),
+            http_only: cookie.httponly(),
+            secure: cookie.secure(),
+            same_site: cookie.same_site(),
+            comment: None,
+            invalid_expires: None,
+        }
+    }
+
+    // the cookie, unless its expires is not a date or its name or value would not parse back
+    fn checked(&self) -> Result<Cookie, HarError> {
+        if let Some(ref expires) = self.invalid_expires {
+            return Err(HarError::InvalidExpires(expires.clone()));
+        }
+        if !validate::is_storable_pair(&self.name, &self.value) {
+            return Err(HarError::InvalidPair);
+        }
+        Ok(Cookie::from(self))
+    }
+}
+
+impl<'a> From<&'a HarCookie> for Cookie {
+    fn from(har: &'a HarCookie) -> Cookie {
+        let mut builder = CookieBuilder::new(&har.name[..], &har.value[..])
+            .httponly(har.http_only)
+            .secure(har.secure)
+            .same_site(har.same_site)
+            .expires(har.expires);
+        if let Some(ref domain) = har.domain {
+            builder = builder.domain(&domain[..]);
+        }
+        if let Some(ref path) = har.path {
+            builder = builder.path(&path[..]);
+        }
+        builder.build()
+    }
+}
+
+impl CookieJar {
+    // stores the cookies of every entry in the log, in order, as though each had been set by
+    // the entry's request url: first those the request sent, then those its response set.
+    // returns the cookies which were not stored: those of entries whose url does not parse,
+    // those whose expires is not a date or whose name or value is invalid, and those the jar
+    // would not accept from that url
+    pub fn import_har(&mut self, har: &Har) -> Vec<HarRejection> {
+        let mut rejected = Vec::new();
+        for (i, entry) in har.log.entries.iter().enumerate() {
+            let url = Url::parse(&entry.request.url);
+            for har_cookie in entry.request.cookies.iter().chain(&entry.response.cookies) {
+                let result = match url {
+                    Ok(ref url) => {
+                        har_cookie.checked()
+                            .and_then(|cookie| self.store(cookie, url).map_err(HarError::Store))
+                    }
+                    Err(e) => Err(HarError::InvalidUrl(e)),
+                };
+                if let Err(error) = result {
+                    rejected.push(HarRejection {
+                        entry: i,
+                        name: har_cookie.name.clone(),
+                        error,
+                    });
+                }
+            }
+        }
+        rejected
+    }
+
+    // the cookies of the jar as HAR cookies, scoped as the jar scoped them: the domain of a
+    // domain cookie is written with a leading '.', that of a host-only cookie without one.
+    // Partitioned cookies are left out, since HAR has no place for the partition, as are
+    // cookies which have expired but not yet been removed
+    pub fn to_har_cookies(&self) -> Vec<HarCookie> {
+        let now = Timestamp::now();
+        self.iter()
+            .filter(|stored| stored.partition_key().is_none() && !stored.is_expired(now))
+            .map(|stored| {
+                let cookie = stored.cookie();
+                let dot = if stored.host_only() { "" } else { "." };
+                HarCookie {
+                    name: cookie.name().to_owned(),
+                    value: cookie.value().to_owned(),
+                    path: Some(stored.path().to_owned()),
+                    domain: Some(format!("{}{}", dot, stored.domain())),
+                    expires: stored.expiry_time(),
+                    http_only: cookie.httponly(),
+                    secure: cookie.secure(),
+                    same_site: cookie.same_site(),
+                    comment: None,
+                    invalid_expires: None,
+                }
+            })
+            .collect()
+    }
+}
+
+// `expires` as an ISO 8601 string. reading falls back to the cookie-date algorithm, so that
+// dates some tools write in that form are not taken for invalid ones
+mod iso8601 {
+    use serde_crate::ser::Serializer;
+
+    use {parse_cookie_date, Timestamp};
+
+    pub fn serialize<S>(expires: &Option<Timestamp>, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match *expires {
+            Some(ref t) => serializer.serialize_str(&t.to_iso8601()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Timestamp> {
+        Timestamp::parse_iso8601(s).or_else(|| parse_cookie_date(s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use url::Url;
+
+    use super::{Har, HarCookie, HarError};
+    use {Cookie, CookieJar, SameSite, StoreError, Timestamp};
+
+    #[test]
+    fn cookies() {
+        let c = Cookie::parse("sid=abc; Domain=example.com; Path=/; Secure; HttpOnly; \
+                               SameSite=Lax; Expires=Thu, 22 Mar 2012 14:53:18 GMT")
+            .unwrap();
+        let now = Timestamp::from_unix(1_000_000_000);
+        let har = HarCookie::from_cookie(&c, now);
+        assert_eq!(serde_json::to_value(&har).unwrap(),
+                   json!({
+                       "name": "sid",
+                       "value": "abc",
+                       "path": "/",
+                       "domain": "example.com",
+                       "expires": "2012-03-22T14:53:18.000Z",
+                       "httpOnly": true,
+                       "secure": true,
+                       "sameSite": "Lax",
+                   }));
+        assert_eq!(Cookie::from(&har).as_str(),
+                   "sid=abc; Domain=example.com; Path=/; Secure; HttpOnly; SameSite=Lax; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+
+        // Max-Age becomes the time it runs out at, taking precedence over Expires as it does
+        // in a user agent
+        let har = HarCookie::from_cookie(&Cookie::parse("a=1; Max-Age=60").unwrap(), now);
+        assert_eq!(har.expires, Some(Timestamp::from_unix(1_000_000_060)));
+        assert_eq!(serde_json::to_value(&har).unwrap()["expires"],
+                   json!("2001-09-09T01:47:40.000Z"));
+        let har = HarCookie::from_cookie(&Cookie::parse("a=1; Max-Age=0; \
+                                                         Expires=Thu, 22 Mar 2012 14:53:18 GMT")
+                                             .unwrap(),
+                                         now);
+        assert_eq!(har.expires.map(|t| t.to_iso8601()),
+                   Some("1900-01-01T00:00:00.000Z".to_owned()));
+        assert_eq!(HarCookie::from_cookie(&Cookie::new("a", "1"), now).expires, None);
+
+        let har: HarCookie = serde_json::from_str(r#"{"name": "theme", "value": "dark",
+                                                      "expires": "2012-03-22T16:53:18.5+02:00",
+                                                      "comment": ""}"#)
+            .unwrap();
+        assert_eq!(har.expires.map(|t| t.unix_secs()), Some(1_332_427_998));
+        assert!(!har.secure);
+        assert_eq!(Cookie::from(&har).as_str(),
+                   "theme=dark; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+
+        let session: HarCookie = serde_json::from_str(r#"{"name": "a", "value": "1",
+                                                          "expires": null, "sameSite": "None"}"#)
+            .unwrap();
+        assert_eq!((session.expires, session.same_site), (None, Some(SameSite::None)));
+        let empty: HarCookie = serde_json::from_str(r#"{"name": "a", "value": "1",
+                                                        "expires": ""}"#)
+            .unwrap();
+        assert_eq!((empty.expires, empty.invalid_expires), (None, None));
+        let rfc1123: HarCookie = serde_json::from_str(r#"{"name": "a", "value": "1",
+                                                          "expires":
+                                                          "Thu, 22 Mar 2012 14:53:18 GMT"}"#)
+            .unwrap();
+        assert_eq!(rfc1123.expires.map(|t| t.unix_secs()), Some(1_332_427_998));
+        let unknown: HarCookie = serde_json::from_str(r#"{"name": "a", "value": "1",
+                                                          "expires": "tomorrow"}"#)
+            .unwrap();
+        assert_eq!(unknown.expires, None);
+        assert_eq!(unknown.invalid_expires, Some("tomorrow".to_owned()));
+        assert_eq!(serde_json::to_value(&unknown).unwrap(),
+                   json!({"name": "a", "value": "1", "httpOnly": false, "secure": false}));
+        assert!(serde_json::from_str::<HarCookie>(r#"{"name": "a", "value": "1",
+                                                      "expires": 42}"#)
+            .is_err());
+
+        // past the last four-digit year, written as that year so that it reads back
+        let late: HarCookie = serde_json::from_str(r#"{"name": "a", "value": "1",
+                                                       "expires":
+                                                       "9999-12-31T23:59:59-23:59"}"#)
+            .unwrap();
+        let json = serde_json::to_string(&late).unwrap();
+        assert!(json.contains("\"expires\":\"9999-12-31T23:59:59.000Z\""));
+        let back: HarCookie = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.expires, Timestamp::parse_iso8601("9999-12-31T23:59:59Z"));
+    }
+
+    #[test]
+    fn import() {
+        let expires = Timestamp::now().saturating_add_secs(3600).to_iso8601();
+        let har: Har = serde_json::from_value(json!({
+                "log": {
+                    "version": "1.2",
+                    "creator": {"name": "test", "version": "1"},
+                    "entries": [{
+                        "startedDateTime": "2012-03-22T14:53:18.000Z",
+                        "request": {
+                            "method": "GET",
+                            "url": "https://www.example.com/account/login",
+                            "cookies": [{"name": "theme", "value": "dark"}],
+                        },
+                        "response": {
+                            "status": 200,
+                            "cookies": [
+                                {"name": "sid", "value": "abc", "path": "/",
+                                 "domain": ".example.com", "expires": expires,
+                                 "httpOnly": true, "secure": true},
+                                {"name": "bad", "value": "1", "domain": "other.com"},
+                            ],
+                        },
+                    }, {
+                        "request": {"url": "not a url", "cookies": [{"name": "x", "value": "1"}]},
+                        "response": {"cookies": []},
+                    }, {
+                        "request": {"url": "https://www.example.com/account/", "cookies": []},
+                        "response": {
+                            "cookies": [
+                                {"name": "theme", "value": "light"},
+                                {"name": "pref", "value": "1", "path": "/",
+                                 "expires": "Wed, 18 May 2033 03:33:20 GMT"},
+                                {"name": "tmp", "value": "1", "path": "/", "expires": "soon"},
+                                {"name": "a;b", "value": "1"},
+                            ],
+                        },
+                    }],
+                },
+            }))
+            .unwrap();
+
+        let mut jar = CookieJar::new();
+        let rejected = jar.import_har(&har);
+        let rejected: Vec<_> = rejected.iter().map(|r| (r.entry, &r.name[..], &r.error)).collect();
+        match rejected[..] {
+            [(0, "bad", &HarError::Store(StoreError::DomainMismatch)),
+             (1, "x", &HarError::InvalidUrl(_)),
+             (2, "tmp", HarError::InvalidExpires(soon)),
+             (2, "a;b", &HarError::InvalidPair)] => assert_eq!(soon, "soon"),
+            _ => panic!("{:?}", rejected),
+        }
+        assert_eq!(jar.len(), 3);
+        let sid = jar.iter().find(|c| c.cookie().name() == "sid").unwrap();
+        assert_eq!(sid.domain(), "example.com");
+        assert!(!sid.host_only() && sid.persistent() && sid.cookie().httponly());
+        let sid_expires = sid.expiry_time();
+        let url = Url::parse("https://www.example.com/account/x").unwrap();
+        let pref = jar.iter().find(|c| c.cookie().name() == "pref").unwrap();
+        assert_eq!(pref.expiry_time(), Some(Timestamp::from_unix(2_000_000_000)));
+        assert_eq!(jar.request_header(&url), Some("theme=light; sid=abc; pref=1".to_owned()));
+
+        // the host-only theme keeps its host and the default path it was scoped to
+        let exported = jar.to_har_cookies();
+        let scopes: Vec<_> = exported.iter()
+            .map(|c| (&c.name[..], &c.domain.as_ref().unwrap()[..], &c.path.as_ref().unwrap()[..]))
+            .collect();
+        assert_eq!(scopes,
+                   [("sid", ".example.com", "/"),
+                    ("theme", "www.example.com", "/account"),
+                    ("pref", "www.example.com", "/")]);
+        assert_eq!(exported[0].expires, sid_expires);
+        assert!(exported[0].http_only && exported[0].secure);
+    }
+}
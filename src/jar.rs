@@ -0,0 +1,647 @@
+use std::error::Error;
+use std::fmt;
+
+use url::{Host, Url};
+
+use validate;
+use {cookie_header, Cookie, Priority, Timestamp, ValidationError};
+
+// the number of cookies Chromium keeps per domain before evicting
+const DEFAULT_DOMAIN_LIMIT: usize = 180;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreError {
+    // the request-uri has no host to scope the cookie to
+    NoHost,
+    // the Domain attribute does not domain-match the request host
+    DomainMismatch,
+    // a `__Secure-` or `__Host-` prefixed cookie from a request that was not secure
+    InsecureOrigin,
+    // a `__Secure-` or `__Host-` prefixed cookie without the attributes its prefix requires
+    Prefix(ValidationError),
+    // a Partitioned cookie without Secure
+    PartitionedInsecure,
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            StoreError::NoHost => write!(f, "request url has no host"),
+            StoreError::DomainMismatch => {
+                write!(f, "cookie Domain does not domain-match the request host")
+            }
+            StoreError::InsecureOrigin => {
+                write!(f, "prefixed cookie was not received over a secure connection")
+            }
+            StoreError::Prefix(ref e) => e.fmt(f),
+            StoreError::PartitionedInsecure => {
+                write!(f, "cookie is Partitioned but is not Secure")
+            }
+        }
+    }
+}
+
+impl Error for StoreError {}
+
+// a cookie along with the state the storage model of RFC 6265 §5.3 keeps for it
+pub struct StoredCookie {
+    cookie: Cookie,
+    domain: String,
+    path: String,
+    host_only: bool,
+    // the top-level site a Partitioned cookie was set under, and is only sent beneath
+    partition_key: Option<String>,
+    // None for a non-persistent (session) cookie
+    expiry_time: Option<Timestamp>,
+    creation_time: Timestamp,
+    last_access_time: Timestamp,
+}
+
+impl StoredCookie {
+    // a cookie read back from another store, such as a cookies.txt file, which already scoped
+    // it to a domain and path; it is unpartitioned, and created and accessed at `now`
+    pub(crate) fn restored(cookie: Cookie,
+                           domain: String,
+                           path: String,
+                           host_only: bool,
+                           expiry_time: Option<Timestamp>,
+                           now: Timestamp)
+                           -> StoredCookie {
+        StoredCookie {
+            cookie,
+            domain,
+            path,
+            host_only,
+            partition_key: None,
+            expiry_time,
+            creation_time: now,
+            last_access_time: now,
+        }
+    }
+
+    pub fn cookie(&self) -> &Cookie {
+        &self.cookie
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn host_only(&self) -> bool {
+        self.host_only
+    }
+
+    pub fn partition_key(&self) -> Option<&str> {
+        self.partition_key.as_ref().map(|k| &k[..])
+    }
+
+    pub fn persistent(&self) -> bool {
+        self.expiry_time.is_some()
+    }
+
+    pub fn expiry_time(&self) -> Option<Timestamp> {
+        self.expiry_time
+    }
+
+    pub fn creation_time(&self) -> Timestamp {
+        self.creation_time
+    }
+
+    pub fn last_access_time(&self) -> Timestamp {
+        self.last_access_time
+    }
+
+    pub fn priority(&self) -> Priority {
+        self.cookie.priority().unwrap_or(Priority::Medium)
+    }
+
+    pub(crate) fn is_expired(&self, now: Timestamp) -> bool {
+        self.expiry_time.is_some_and(|e| e <= now)
+    }
+
+    fn matches(&self, host: &str, path: &str, secure: bool, partition_key: &str) -> bool {
+        if self.partition_key.as_ref().is_some_and(|k| k != partition_key) {
+            return false;
+        }
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(host, &self.domain)
+        };
+        domain_ok && path_match(path, &self.path) && (secure || !self.cookie.secure())
+    }
+}
+
+pub struct CookieJar {
+    cookies: Vec<StoredCookie>,
+    domain_limit: usize,
+}
+
+impl Default for CookieJar {
+    fn default() -> CookieJar {
+        CookieJar {
+            cookies: Vec::new(),
+            domain_limit: DEFAULT_DOMAIN_LIMIT,
+        }
+    }
+}
+
+impl CookieJar {
+    pub fn new() -> CookieJar {
+        CookieJar::default()
+    }
+
+    // the most cookies kept for any one domain; storing past it evicts expired cookies first,
+    // then the lowest Priority, and within a Priority the least recently accessed
+    pub fn with_domain_limit(mut self, limit: usize) -> CookieJar {
+        self.domain_limit = limit;
+        self
+    }
+
+    pub fn domain_limit(&self) -> usize {
+        self.domain_limit
+    }
+
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, StoredCookie> {
+        self.cookies.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+    }
+
+    // stores a cookie received in a response to `request_url`, replacing any cookie with the
+    // same name, domain and path. a cookie which is already expired is not kept, so this is
+    // also how servers delete cookies
+    pub fn store(&mut self, cookie: Cookie, request_url: &Url) -> Result<(), StoreError> {
+        self.store_at(cookie, request_url, request_url, Timestamp::now())
+    }
+
+    // as `store`, for a request made beneath the top-level page at `top_level_url`, as for an
+    // embedded third-party frame. a Partitioned cookie is keyed by that top-level site
+    pub fn store_partitioned(&mut self,
+                             cookie: Cookie,
+                             request_url: &Url,
+                             top_level_url: &Url)
+                             -> Result<(), StoreError> {
+        self.store_at(cookie, request_url, top_level_url, Timestamp::now())
+    }
+
+    pub(crate) fn store_at(&mut self,
+                           cookie: Cookie,
+                           request_url: &Url,
+                           top_level_url: &Url,
+                           now: Timestamp)
+                           -> Result<(), StoreError> {
+        let host = canonical_host(request_url).ok_or(StoreError::NoHost)?;
+        validate::check_prefix(&cookie).map_err(StoreError::Prefix)?;
+        if validate::has_name_prefix(cookie.name()) && !is_secure_scheme(request_url) {
+            return Err(StoreError::InsecureOrigin);
+        }
+
+        let partition_key = if cookie.partitioned() {
+            if !cookie.secure() {
+                return Err(StoreError::PartitionedInsecure);
+            }
+            Some(site(top_level_url).ok_or(StoreError::NoHost)?)
+        } else {
+            None
+        };
+
+        let expiry_time = cookie.expiry_at(now);
+
+        let (domain, host_only) = match cookie.domain() {
+            Some(domain) => {
+                let domain = domain.strip_prefix('.').unwrap_or(domain).to_lowercase();
+                if !domain_match(&host, &domain) {
+                    return Err(StoreError::DomainMismatch);
+                }
+                (domain, false)
+            }
+            None => (host, true),
+        };
+
+        let path = match cookie.path() {
+            Some(path) if path.starts_with('/') => path.to_owned(),
+            _ => default_path(request_url).to_owned(),
+        };
+
+        self.insert_at(StoredCookie {
+                           cookie,
+                           domain,
+                           path,
+                           host_only,
+                           partition_key,
+                           expiry_time,
+                           creation_time: now,
+                           last_access_time: now,
+                       },
+                       now);
+        Ok(())
+    }
+
+    // adds a cookie whose storage state is already worked out, replacing any cookie with the
+    // same name, domain, path and partition but keeping that one's creation time
+    pub(crate) fn insert_at(&mut self, mut stored: StoredCookie, now: Timestamp) {
+        if let Some(i) = self.cookies.iter().position(|c| {
+            c.cookie.name() == stored.cookie.name() && c.domain == stored.domain &&
+            c.path == stored.path && c.partition_key == stored.partition_key
+        }) {
+            stored.creation_time = self.cookies.remove(i).creation_time;
+        }
+        let domain = stored.domain.clone();
+        self.cookies.push(stored);
+        self.remove_expired_at(now);
+        self.evict_over_limit(&domain);
+    }
+
+    fn evict_over_limit(&mut self, domain: &str) {
+        let mut count = self.cookies.iter().filter(|c| c.domain == domain).count();
+        while count > self.domain_limit {
+            let victim = self.cookies
+                .iter()
+                .enumerate()
+                .filter(|&(_, c)| c.domain == domain)
+                .min_by_key(|&(_, c)| (c.priority(), c.last_access_time))
+                .map(|(i, _)| i)
+                .unwrap();
+            self.cookies.remove(victim);
+            count -= 1;
+        }
+    }
+
+    pub fn remove_expired(&mut self) {
+        self.remove_expired_at(Timestamp::now());
+    }
+
+    fn remove_expired_at(&mut self, now: Timestamp) {
+        self.cookies.retain(|c| !c.is_expired(now));
+    }
+
+    // the cookies to send on a request to `url`, in the order of RFC 6265 §5.4: longer paths
+    // first, then earlier creation times. this updates their last-access times
+    pub fn cookies_for(&mut self, url: &Url) -> Vec<&Cookie> {
+        self.cookies_for_at(url, url, Timestamp::now())
+    }
+
+    // as `cookies_for`, for a request made beneath the top-level page at `top_level_url`.
+    // Partitioned cookies are only included if they were set under the same top-level site
+    pub fn cookies_for_partitioned(&mut self, url: &Url, top_level_url: &Url) -> Vec<&Cookie> {
+        self.cookies_for_at(url, top_level_url, Timestamp::now())
+    }
+
+    pub(crate) fn cookies_for_at(&mut self,
+                                 url: &Url,
+                                 top_level_url: &Url,
+                                 now: Timestamp)
+                                 -> Vec<&Cookie> {
+        self.remove_expired_at(now);
+        let (host, partition_key) = match (canonical_host(url), site(top_level_url)) {
+            (Some(host), Some(partition_key)) => (host, partition_key),
+            _ => return Vec::new(),
+        };
+        let path = url.path();
+        let secure = is_secure_scheme(url);
+
+        let mut matched: Vec<&mut StoredCookie> = self.cookies
+            .iter_mut()
+            .filter(|c| c.matches(&host, path, secure, &partition_key))
+            .collect();
+        matched.sort_by(|a, b| {
+            b.path.len().cmp(&a.path.len()).then(a.creation_time.cmp(&b.creation_time))
+        });
+        matched.into_iter()
+            .map(|c| {
+                c.last_access_time = now;
+                &c.cookie
+            })
+            .collect()
+    }
+
+    // the value of the Cookie header for a request to `url`, if any cookies apply
+    pub fn request_header(&mut self, url: &Url) -> Option<String> {
+        to_header(self.cookies_for(url))
+    }
+
+    pub fn request_header_partitioned(&mut self,
+                                      url: &Url,
+                                      top_level_url: &Url)
+                                      -> Option<String> {
+        to_header(self.cookies_for_partitioned(url, top_level_url))
+    }
+}
+
+fn to_header(cookies: Vec<&Cookie>) -> Option<String> {
+    if cookies.is_empty() {
+        None
+    } else {
+        Some(cookie_header(cookies))
+    }
+}
+
+fn canonical_host(url: &Url) -> Option<String> {
+    match url.host() {
+        Some(Host::Domain(domain)) => Some(domain.to_lowercase()),
+        Some(Host::Ipv4(addr)) => Some(addr.to_string()),
+        Some(Host::Ipv6(addr)) => Some(format!("[{}]", addr)),
+        None => None,
+    }
+}
+
+// the partition key for a top-level page. this is the scheme and host rather than the
+// registrable domain, as no public suffix list is available; partitions are therefore
+// somewhat narrower than a browser's
+fn site(url: &Url) -> Option<String> {
+    canonical_host(url).map(|host| format!("{}://{}", url.scheme(), host))
+}
+
+fn is_secure_scheme(url: &Url) -> bool {
+    url.scheme() == "https" || url.scheme() == "wss"
+}
+
+fn is_ip_address(host: &str) -> bool {
+    host.starts_with('[') || host.parse::<::std::net::Ipv4Addr>().is_ok()
+}
+
+// §5.1.3
+fn domain_match(host: &str, domain: &str) -> bool {
+    host == domain ||
+    (!is_ip_address(host) && host.ends_with(domain) &&
+     host[..host.len() - domain.len()].ends_with('.'))
+}
+
+// §5.1.4
+fn default_path(url: &Url) -> &str {
+    let path = url.path();
+    if !path.starts_with('/') {
+        return "/";
+    }
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+// §5.1.4
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path ||
+    (request_path.starts_with(cookie_path) &&
+     (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{default_path, domain_match, path_match, CookieJar, StoreError};
+    use {Cookie, Priority, Timestamp, ValidationError};
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn names(cookies: Vec<&Cookie>) -> Vec<&str> {
+        cookies.into_iter().map(|c| c.name()).collect()
+    }
+
+    #[test]
+    fn matching() {
+        assert!(domain_match("example.com", "example.com"));
+        assert!(domain_match("www.example.com", "example.com"));
+        assert!(!domain_match("wwwexample.com", "example.com"));
+        assert!(!domain_match("example.com", "www.example.com"));
+        assert!(!domain_match("1.2.3.4", "2.3.4"));
+        assert!(domain_match("1.2.3.4", "1.2.3.4"));
+
+        assert!(path_match("/", "/"));
+        assert!(path_match("/foo", "/"));
+        assert!(path_match("/foo/bar", "/foo"));
+        assert!(path_match("/foo/bar", "/foo/"));
+        assert!(!path_match("/foobar", "/foo"));
+        assert!(!path_match("/", "/foo"));
+
+        assert_eq!(default_path(&url("http://example.com")), "/");
+        assert_eq!(default_path(&url("http://example.com/foo")), "/");
+        assert_eq!(default_path(&url("http://example.com/foo/")), "/foo");
+        assert_eq!(default_path(&url("http://example.com/foo/bar?q=/x")), "/foo");
+    }
+
+    #[test]
+    fn store_and_retrieve() {
+        let mut jar = CookieJar::new();
+        let request = url("http://www.example.com/account/login");
+        jar.store(Cookie::parse("host=1").unwrap(), &request).unwrap();
+        jar.store(Cookie::parse("dom=2; Domain=EXAMPLE.com; Path=/").unwrap(), &request)
+            .unwrap();
+        assert_eq!(jar.store(Cookie::parse("bad=3; Domain=other.com").unwrap(), &request),
+                   Err(StoreError::DomainMismatch));
+        assert_eq!(jar.len(), 2);
+
+        let host = jar.iter().find(|c| c.cookie().name() == "host").unwrap();
+        assert!(host.host_only());
+        assert_eq!(host.domain(), "www.example.com");
+        assert_eq!(host.path(), "/account");
+        assert!(!host.persistent());
+        let dom = jar.iter().find(|c| c.cookie().name() == "dom").unwrap();
+        assert!(!dom.host_only());
+        assert_eq!(dom.domain(), "example.com");
+
+        assert_eq!(names(jar.cookies_for(&url("http://www.example.com/account/x"))),
+                   vec!["host", "dom"]);
+        assert_eq!(names(jar.cookies_for(&url("http://www.example.com/"))), vec!["dom"]);
+        assert_eq!(names(jar.cookies_for(&url("http://sub.example.com/account"))),
+                   vec!["dom"]);
+        assert!(jar.cookies_for(&url("http://example.org/account")).is_empty());
+        assert_eq!(jar.request_header(&url("http://www.example.com/account")),
+                   Some("host=1; dom=2".to_owned()));
+        assert_eq!(jar.request_header(&url("http://example.org/")), None);
+    }
+
+    #[test]
+    fn secure_only() {
+        let mut jar = CookieJar::new();
+        jar.store(Cookie::parse("a=1; Secure; Path=/").unwrap(),
+                   &url("https://example.com/"))
+            .unwrap();
+        assert!(jar.cookies_for(&url("http://example.com/")).is_empty());
+        assert_eq!(names(jar.cookies_for(&url("https://example.com/"))), vec!["a"]);
+        assert_eq!(names(jar.cookies_for(&url("wss://example.com/"))), vec!["a"]);
+    }
+
+    #[test]
+    fn ordering_and_replacement() {
+        let mut jar = CookieJar::new();
+        let r = url("http://example.com/");
+        let t = Timestamp::from_unix;
+        jar.store_at(Cookie::parse("a=1; Path=/").unwrap(), &r, &r, t(10)).unwrap();
+        jar.store_at(Cookie::parse("b=1; Path=/foo").unwrap(), &r, &r, t(11)).unwrap();
+        jar.store_at(Cookie::parse("c=1; Path=/").unwrap(), &r, &r, t(12)).unwrap();
+        jar.store_at(Cookie::parse("a=2; Path=/foo").unwrap(), &r, &r, t(13)).unwrap();
+        let cookies = jar.cookies_for_at(&url("http://example.com/foo/bar"), &r, t(20));
+        assert_eq!(names(cookies), vec!["b", "a", "a", "c"]);
+
+        // replacing a cookie keeps its creation time, and so its place in the ordering
+        jar.store_at(Cookie::parse("a=3; Path=/").unwrap(), &r, &r, t(14)).unwrap();
+        assert_eq!(jar.len(), 4);
+        let cookies = jar.cookies_for_at(&url("http://example.com/"), &r, t(21));
+        assert_eq!(cookies.iter().map(|c| c.cookie_pair()).collect::<Vec<_>>(),
+                   vec![("a", "3"), ("c", "1")]);
+        let a = jar.iter().find(|c| c.cookie().cookie_pair() == ("a", "3")).unwrap();
+        assert_eq!(a.creation_time(), t(10));
+        assert_eq!(a.last_access_time(), t(21));
+    }
+
+    #[test]
+    fn domain_limit() {
+        let mut jar = CookieJar::new().with_domain_limit(3);
+        assert_eq!(CookieJar::new().domain_limit(), 180);
+        let r = url("http://www.example.com/");
+        let other = url("http://example.org/");
+        let t = Timestamp::from_unix;
+        jar.store_at(Cookie::parse("auth=1; Priority=High").unwrap(), &r, &r, t(1)).unwrap();
+        jar.store_at(Cookie::parse("a=1").unwrap(), &r, &r, t(2)).unwrap();
+        jar.store_at(Cookie::parse("b=1; Priority=Low").unwrap(), &r, &r, t(3)).unwrap();
+        jar.store_at(Cookie::parse("o=1").unwrap(), &other, &other, t(4)).unwrap();
+        jar.store_at(Cookie::parse("d=1; Domain=example.com").unwrap(), &r, &r, t(5)).unwrap();
+        assert_eq!(jar.len(), 5);
+
+        // the Low priority cookie goes first, even though it is the most recently stored
+        jar.store_at(Cookie::parse("c=1; Priority=medium").unwrap(), &r, &r, t(6)).unwrap();
+        let mut kept: Vec<_> = jar.iter().map(|c| c.cookie().name()).collect();
+        kept.sort();
+        assert_eq!(kept, vec!["a", "auth", "c", "d", "o"]);
+
+        // then the least recently accessed among equal priorities
+        jar.cookies_for_at(&url("http://www.example.com/"), &r, t(7));
+        jar.store_at(Cookie::parse("e=1; Priority=Low").unwrap(), &r, &r, t(8)).unwrap();
+        assert!(jar.iter().all(|c| c.cookie().name() != "e"));
+        jar.store_at(Cookie::parse("f=1").unwrap(), &r, &r, t(9)).unwrap();
+        let mut kept: Vec<_> = jar.iter().map(|c| c.cookie().name()).collect();
+        kept.sort();
+        assert_eq!(kept, vec!["auth", "c", "d", "f", "o"]);
+        assert_eq!(jar.iter().find(|c| c.cookie().name() == "auth").unwrap().priority(),
+                   Priority::High);
+    }
+
+    #[test]
+    fn expiry() {
+        let mut jar = CookieJar::new();
+        let request = url("http://example.com/");
+        let now = Timestamp::now();
+        jar.store_at(Cookie::parse("a=1; Max-Age=60").unwrap(), &request, &request, now).unwrap();
+        jar.store_at(Cookie::parse("b=1; Expires=Thu, 22 Mar 2012 14:53:18 GMT").unwrap(),
+                      &request,
+                      &request,
+                      now)
+            .unwrap();
+        assert_eq!(jar.len(), 1);
+        let a = jar.iter().next().unwrap();
+        assert!(a.persistent());
+        assert_eq!(a.expiry_time(), Some(now.saturating_add_secs(60)));
+
+        let later = |secs| Timestamp::from_unix(now.unix_secs() + secs);
+        assert_eq!(jar.cookies_for_at(&request, &request, later(59)).len(), 1);
+        assert_eq!(jar.cookies_for_at(&request, &request, later(61)).len(), 0);
+        assert!(jar.is_empty());
+
+        // an expired cookie deletes the one it replaces
+        jar.store_at(Cookie::parse("a=1").unwrap(), &request, &request, now).unwrap();
+        let mut deletion = Cookie::new("a", "");
+        deletion.expire();
+        jar.store_at(deletion, &request, &request, now).unwrap();
+        assert!(jar.is_empty());
+
+        // Max-Age takes precedence over Expires, even when it has already passed
+        jar.store_at(Cookie::parse("a=1; Max-Age=0; Expires=Thu, 22 Mar 2112 14:53:18 GMT")
+                          .unwrap(),
+                      &request,
+                      &request,
+                      now)
+            .unwrap();
+        jar.store_at(Cookie::parse("b=1; Max-Age=-1").unwrap(), &request, &request, now).unwrap();
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn prefixes() {
+        let mut jar = CookieJar::new();
+        let request = url("https://www.example.com/a/b");
+        jar.store(Cookie::parse("__Host-a=1; Secure; Path=/").unwrap(), &request).unwrap();
+        jar.store(Cookie::parse("__Secure-b=1; Secure; Domain=example.com").unwrap(),
+                   &request)
+            .unwrap();
+        assert_eq!(jar.store(Cookie::parse("__Host-c=1; Secure").unwrap(), &request),
+                   Err(StoreError::Prefix(ValidationError::HostPrefixPath)));
+        assert_eq!(jar.store(Cookie::parse("__Host-c=1; Secure; Path=/; Domain=example.com")
+                                  .unwrap(),
+                              &request),
+                   Err(StoreError::Prefix(ValidationError::HostPrefixDomain)));
+        assert_eq!(jar.store(Cookie::parse("__SECURE-c=1").unwrap(), &request),
+                   Err(StoreError::Prefix(ValidationError::SecurePrefixInsecure)));
+        assert_eq!(jar.store(Cookie::parse("__Secure-c=1; Secure").unwrap(),
+                              &url("http://www.example.com/")),
+                   Err(StoreError::InsecureOrigin));
+        assert_eq!(jar.len(), 2);
+    }
+
+    #[test]
+    fn partitioned() {
+        let mut jar = CookieJar::new();
+        let embed = url("https://widget.example/frame");
+        let site_a = url("https://a.example/page");
+        let site_b = url("https://b.example/page");
+        jar.store_partitioned(Cookie::parse("chip=a; Secure; Partitioned; Path=/").unwrap(),
+                               &embed,
+                               &site_a)
+            .unwrap();
+        jar.store_partitioned(Cookie::parse("chip=b; Secure; Partitioned; Path=/").unwrap(),
+                               &embed,
+                               &site_b)
+            .unwrap();
+        jar.store_partitioned(Cookie::parse("shared=1; Secure; Path=/").unwrap(),
+                               &embed,
+                               &site_a)
+            .unwrap();
+        assert_eq!(jar.store_partitioned(Cookie::parse("chip=c; Partitioned").unwrap(),
+                                          &embed,
+                                          &site_a),
+                   Err(StoreError::PartitionedInsecure));
+        assert_eq!(jar.len(), 3);
+        assert_eq!(jar.iter().filter_map(|c| c.partition_key()).collect::<Vec<_>>(),
+                   vec!["https://a.example", "https://b.example"]);
+
+        assert_eq!(jar.request_header_partitioned(&embed, &site_a),
+                   Some("chip=a; shared=1".to_owned()));
+        assert_eq!(jar.request_header_partitioned(&embed, &site_b),
+                   Some("chip=b; shared=1".to_owned()));
+        assert_eq!(jar.request_header_partitioned(&embed, &url("https://c.example/")),
+                   Some("shared=1".to_owned()));
+        assert_eq!(jar.request_header(&embed), Some("shared=1".to_owned()));
+
+        // set as a first party, a Partitioned cookie is keyed by its own site
+        jar.store(Cookie::parse("chip=own; Secure; Partitioned; Path=/").unwrap(), &embed)
+            .unwrap();
+        assert_eq!(jar.request_header(&embed), Some("shared=1; chip=own".to_owned()));
+        assert_eq!(jar.request_header_partitioned(&embed, &site_a),
+                   Some("chip=a; shared=1".to_owned()));
+    }
+
+    #[test]
+    fn no_host() {
+        let mut jar = CookieJar::new();
+        assert_eq!(jar.store(Cookie::new("a", "1"), &url("data:text/plain,foo")),
+                   Err(StoreError::NoHost));
+    }
+}
@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::fmt;
+
+use getrandom;
+
+pub const KEY_LEN: usize = 32;
+
+// secret key material; deliberately without Debug so it cannot end up in logs
+#[derive(Clone)]
+pub struct Key([u8; KEY_LEN]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyLengthError(pub usize);
+
+impl fmt::Display for KeyLengthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "key must be {} bytes, got {}", KEY_LEN, self.0)
+    }
+}
+
+impl Error for KeyLengthError {}
+
+impl Key {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Key, KeyLengthError> {
+        if bytes.len() != KEY_LEN {
+            return Err(KeyLengthError(bytes.len()));
+        }
+        let mut key = [0; KEY_LEN];
+        key.copy_from_slice(bytes);
+        Ok(Key(key))
+    }
+
+    // a key from the operating system's CSPRNG. panics if no randomness is available
+    pub fn generate() -> Key {
+        let mut key = [0; KEY_LEN];
+        getrandom::getrandom(&mut key).expect("failed to obtain randomness for key");
+        Key(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, KeyLengthError};
+
+    #[test]
+    fn key_length() {
+        assert_eq!(Key::from_bytes(&[0; 16]).err(), Some(KeyLengthError(16)));
+        assert_eq!(Key::from_bytes(&[0; 33]).err(), Some(KeyLengthError(33)));
+        assert_eq!(Key::from_bytes(&[9; 32]).unwrap().as_bytes(), &[9; 32][..]);
+        assert!(Key::generate().as_bytes() != Key::generate().as_bytes());
+    }
+}
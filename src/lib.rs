@@ -1,22 +1,92 @@
+#[cfg(feature = "private")]
+extern crate aes_gcm;
+extern crate base64;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(any(feature = "signed", feature = "private"))]
+extern crate getrandom;
+#[cfg(feature = "signed")]
+extern crate hmac;
+extern crate percent_encoding;
+#[cfg(feature = "serde")]
 #[macro_use]
-extern crate lazy_static;
+extern crate serde as serde_crate;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_json;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
+#[cfg(feature = "signed")]
+extern crate sha2;
+#[cfg(feature = "time")]
 extern crate time;
-
+extern crate url;
+
+#[cfg(feature = "sqlite")]
+mod browser;
+mod builder;
+mod cookie_ref;
+mod encoding;
+#[cfg(feature = "serde")]
+mod har;
+mod jar;
+#[cfg(any(feature = "signed", feature = "private"))]
+mod key;
+mod netscape;
+mod pairs;
+mod parse;
+#[cfg(feature = "private")]
+mod private;
+mod redact;
+#[cfg(feature = "serde")]
+pub mod serde;
+#[cfg(feature = "signed")]
+mod signed;
+mod timestamp;
+mod validate;
+
+#[cfg(feature = "sqlite")]
+pub use browser::ImportError;
+pub use builder::CookieBuilder;
+pub use cookie_ref::CookieRef;
+pub use encoding::{DecodeError, Encoding};
+#[cfg(feature = "serde")]
+pub use har::{Har, HarCookie, HarEntry, HarError, HarLog, HarRejection, HarRequest, HarResponse};
+pub use jar::{CookieJar, StoreError, StoredCookie};
+#[cfg(any(feature = "signed", feature = "private"))]
+pub use key::{Key, KeyLengthError};
+pub use netscape::{MalformedLine, NetscapeError};
+pub use pairs::{cookie_header, parse_cookie_header, CookiePairs};
+pub use parse::{parse_cookie_date, Extensions, ParseError};
+#[cfg(feature = "private")]
+pub use private::{DecryptError, PrivateCookies};
+pub use redact::{Redacted, RedactionPolicy};
+#[cfg(feature = "signed")]
+pub use signed::{SignatureError, SignedCookies};
+pub use timestamp::Timestamp;
+pub use validate::ValidationError;
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops::{Range, RangeFrom, RangeTo};
-
-use time::Tm;
-
-lazy_static! {
-    static ref EARLIEST_TM: Tm = time::strptime("1900-01-01T00:00:00Z", "%Y-%m-%dT%H:%M:%SZ")
-            .unwrap();
-}
-
-const DOMAIN_PREFIX: &'static str = "; Domain=";
-const PATH_PREFIX: &'static str = "; Path=";
-const MAX_AGE_PREFIX: &'static str = "; Max-Age=";
-const SECURE_FLAG: &'static str = "; Secure";
-const HTTPONLY_FLAG: &'static str = "; HttpOnly";
-const EXPIRES_PREFIX: &'static str = "; Expires=";
+use std::str::FromStr;
+
+// 1900-01-01T00:00:00Z, what `expire` sets Expires to
+const EARLIEST_TIMESTAMP: Timestamp = Timestamp::from_unix(-2_208_988_800);
+// the range of Expires, 1601-01-01T00:00:00Z to 9999-12-31T23:59:59Z: the four-digit years
+// an IMF-fixdate can hold, from the earliest the cookie-date algorithm accepts
+const MIN_EXPIRES_SECS: i64 = -11_644_473_600;
+const MAX_EXPIRES_SECS: i64 = 253_402_300_799;
+
+const DOMAIN_PREFIX: &str = "; Domain=";
+const PATH_PREFIX: &str = "; Path=";
+const MAX_AGE_PREFIX: &str = "; Max-Age=";
+const SECURE_FLAG: &str = "; Secure";
+const HTTPONLY_FLAG: &str = "; HttpOnly";
+const PARTITIONED_FLAG: &str = "; Partitioned";
+const SAME_SITE_PREFIX: &str = "; SameSite=";
+const PRIORITY_PREFIX: &str = "; Priority=";
+const EXPIRES_PREFIX: &str = "; Expires=";
 
 trait RangeArg {
     fn slice_of<'a>(&self, s: &'a str) -> &'a str;
@@ -43,21 +113,64 @@ impl RangeArg for RangeTo<usize> {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+// Chromium's Priority attribute, which decides what is evicted first when a domain has too
+// many cookies. ordered so that greater is more important; a cookie without the attribute
+// is treated as Medium
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Cookie {
     serialization: String,
     name_end: usize,
     value_end: usize,
     // although ordering of these attributes is not defined in the RFC,
-    // we enforce the ordering is Domain, Path, Secure, HttpOnly, Expires
-    // during serialization. specifically, Secure, HttpOnly, and Expires
-    // are at the end of the serialization as they are all of a known fixed size
-    // when present, with Expires last to simplify replacing its value
+    // we enforce the ordering is Domain, Path, Max-Age, extensions, Secure, HttpOnly,
+    // Partitioned, SameSite, Priority, Expires during serialization. specifically, the flags,
+    // SameSite, Priority, and Expires are at the end of the serialization as they are all of
+    // a known fixed size when present, with Expires last to simplify replacing its value
     domain_end: Option<usize>,
     path_end: Option<usize>,
-    max_age: Option<(u64, usize)>,
+    max_age: Option<(i64, usize)>,
+    // length of the "; name[=value]" run of unrecognized attributes following Max-Age
+    extensions_len: usize,
     secure: bool,
     httponly: bool,
-    expires: Option<Tm>,
+    partitioned: bool,
+    same_site: Option<SameSite>,
+    priority: Option<Priority>,
+    expires: Option<Timestamp>,
 }
 
 impl Cookie {
@@ -69,20 +182,46 @@ impl Cookie {
         Cookie {
             serialization: s,
             name_end: name.len(),
-            value_end: value_end,
+            value_end,
             domain_end: None,
             path_end: None,
             max_age: None,
+            extensions_len: 0,
             secure: false,
             httponly: false,
+            partitioned: false,
+            same_site: None,
+            priority: None,
             expires: None,
         }
     }
 
+    pub fn try_new(name: &str, value: &str) -> Result<Cookie, ValidationError> {
+        validate::check_name(name.trim())?;
+        validate::check_value(value.trim())?;
+        Ok(Cookie::new(name, value))
+    }
+
+    pub fn new_encoded<T>(name: &str, value: T, encoding: Encoding) -> Cookie
+        where T: AsRef<[u8]>
+    {
+        Cookie::new(name, &encoding::encode(value.as_ref(), encoding))
+    }
+
+    pub fn parse(s: &str) -> Result<Cookie, ParseError> {
+        CookieRef::parse(s).map(|c| c.to_owned())
+    }
+
     pub fn as_str(&self) -> &str {
         &self.serialization
     }
 
+    // the serialization, provided the cookie passes `validate`; this catches cookies which
+    // user agents would drop, such as a `__Host-` prefixed name with a Domain
+    pub fn as_checked_str(&self) -> Result<&str, ValidationError> {
+        self.validate().map(|_| self.as_str())
+    }
+
     pub fn name(&self) -> &str {
         self.slice(..self.name_end)
     }
@@ -109,6 +248,11 @@ impl Cookie {
         self
     }
 
+    pub fn try_set_name(&mut self, name: &str) -> Result<&mut Self, ValidationError> {
+        validate::check_name(name.trim())?;
+        Ok(self.set_name(name))
+    }
+
     pub fn value(&self) -> &str {
         self.slice(self.value_start()..self.value_end)
     }
@@ -123,7 +267,7 @@ impl Cookie {
         let old_value_end = self.value_end;
         let suffix = {
             let s = self.slice(old_value_end..);
-            if 0 == s.len() {
+            if s.is_empty() {
                 None
             } else {
                 Some(s.to_owned())
@@ -155,6 +299,21 @@ impl Cookie {
         self
     }
 
+    pub fn try_set_value(&mut self, value: &str) -> Result<&mut Self, ValidationError> {
+        validate::check_value(value.trim())?;
+        Ok(self.set_value(value))
+    }
+
+    pub fn set_value_encoded<T>(&mut self, value: T, encoding: Encoding) -> &mut Self
+        where T: AsRef<[u8]>
+    {
+        self.set_value(&encoding::encode(value.as_ref(), encoding))
+    }
+
+    pub fn value_decoded(&self, encoding: Encoding) -> Result<Vec<u8>, DecodeError> {
+        encoding::decode(self.value(), encoding)
+    }
+
     pub fn cookie_pair(&self) -> (&str, &str) {
         (self.slice(..self.name_end), self.slice(self.value_start()..self.value_end))
     }
@@ -170,7 +329,7 @@ impl Cookie {
 
     #[inline]
     fn domain_end_or_prior(&self) -> usize {
-        self.domain_end.unwrap_or_else(|| self.value_end)
+        self.domain_end.unwrap_or(self.value_end)
     }
 
     pub fn set_domain(&mut self, domain: &str) -> &mut Self {
@@ -206,6 +365,14 @@ impl Cookie {
         self
     }
 
+    pub fn try_set_domain(&mut self, domain: &str) -> Result<&mut Self, ValidationError> {
+        let domain = domain.trim();
+        if !domain.is_empty() {
+            validate::check_domain(domain)?;
+        }
+        Ok(self.set_domain(domain))
+    }
+
     pub fn path(&self) -> Option<&str> {
         self.path_end.and_then(|e| self.path_value_start().map(|s| self.slice(s..e)))
     }
@@ -248,7 +415,12 @@ impl Cookie {
         self
     }
 
-    pub fn max_age(&self) -> Option<u64> {
+    pub fn try_set_path(&mut self, path: &str) -> Result<&mut Self, ValidationError> {
+        validate::check_path(path.trim())?;
+        Ok(self.set_path(path))
+    }
+
+    pub fn max_age(&self) -> Option<i64> {
         self.max_age.map(|(a, _)| a)
     }
 
@@ -266,53 +438,124 @@ impl Cookie {
         self.max_age.map(|_| self.path_end_or_prior() + MAX_AGE_PREFIX.len())
     }
 
-    pub fn set_max_age(&mut self, max_age: u64) -> &mut Self {
-        if self.max_age.map(|(v, _)| v).unwrap_or(0) == max_age {
+    pub fn set_max_age(&mut self, max_age: i64) -> &mut Self {
+        if self.max_age() == Some(max_age) {
             return self;
         }
 
-        let max_age_end = if 0 == max_age {
+        let suffix = if let Some((_, e)) = self.max_age {
+            let s = self.max_age_value_start().unwrap();
+            self.truncate_and_take(s, e)
+        } else {
+            let e = self.path_end_or_prior();
+            let suffix = self.take(e);
+            self.serialization.push_str(MAX_AGE_PREFIX);
+            suffix
+        };
+
+        self.serialization.push_str(&format!("{}", max_age));
+        let max_age_end = self.serialization.len();
+        if let Some(ref s) = suffix {
+            self.serialization.push_str(s);
+        }
+
+        self.max_age = Some((max_age, max_age_end));
+        self
+    }
+
+    pub fn clear_max_age(&mut self) -> &mut Self {
+        if let Some((_, e)) = self.max_age {
             let s = self.path_end_or_prior();
-            let e = self.max_age.map(|(_, e)| e).unwrap();
             self.serialization.drain(s..e);
-            None
-        } else {
-            let suffix = if let Some((_, e)) = self.max_age {
-                let s = self.max_age_value_start().unwrap();
-                self.truncate_and_take(s, e);
-                None
-            } else {
-                let e = self.path_end_or_prior();
-                let suffix = self.take(e);
-                self.serialization.push_str(MAX_AGE_PREFIX);
-                suffix
-            };
-
-            self.serialization.push_str(&format!("{}", max_age));
-            let max_age_end = self.serialization.len();
-            if let Some(ref s) = suffix {
-                self.serialization.push_str(s);
-            }
+            self.max_age = None;
+        }
+        self
+    }
 
-            Some(max_age_end)
-        };
+    pub fn extensions(&self) -> Extensions<'_> {
+        let start = self.max_age_end_or_prior();
+        Extensions::new(self.slice(start..self.extensions_end_or_prior()))
+    }
+
+    pub fn extension(&self, name: &str) -> Option<Option<&str>> {
+        self.extensions().find(|&(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v)
+    }
 
-        self.max_age = max_age_end.map(|e| (max_age, e));
+    #[inline]
+    fn extensions_end_or_prior(&self) -> usize {
+        self.max_age_end_or_prior() + self.extensions_len
+    }
+
+    // sets an attribute this crate does not otherwise know about, replacing any of the same
+    // name (compared case-insensitively) in place, or appending it after the others. a `None`
+    // value emits the bare name, as for a flag. names of the attributes which have their own
+    // setters are ignored
+    pub fn set_extension(&mut self, name: &str, value: Option<&str>) -> &mut Self {
+        let name = name.trim();
+        if name.is_empty() || parse::is_known_attribute(name) {
+            return self;
+        }
+        let value = value.map(str::trim);
+        let mut extensions: Vec<(&str, Option<&str>)> = self.extensions().collect();
+        match extensions.iter().position(|&(n, _)| n.eq_ignore_ascii_case(name)) {
+            Some(i) => extensions[i] = (name, value),
+            None => extensions.push((name, value)),
+        }
+        let rendered = render_extensions(extensions);
+        self.replace_extensions(&rendered);
         self
     }
 
+    pub fn try_set_extension(&mut self,
+                             name: &str,
+                             value: Option<&str>)
+                             -> Result<&mut Self, ValidationError> {
+        validate::check_extension(name.trim(), value.map(str::trim))?;
+        if parse::is_known_attribute(name.trim()) {
+            return Err(ValidationError::InvalidExtension);
+        }
+        Ok(self.set_extension(name, value))
+    }
+
+    pub fn remove_extension(&mut self, name: &str) -> &mut Self {
+        let name = name.trim();
+        if self.extension(name).is_some() {
+            let rendered = render_extensions(self.extensions()
+                .filter(|&(n, _)| !n.eq_ignore_ascii_case(name)));
+            self.replace_extensions(&rendered);
+        }
+        self
+    }
+
+    // appends without looking for an existing attribute of the same name, so that a parsed
+    // cookie keeps repeated extensions as sent
+    pub(crate) fn push_extension(&mut self, name: &str, value: Option<&str>) -> &mut Self {
+        let rendered = render_extensions(Some((name, value)));
+        let e = self.extensions_end_or_prior();
+        self.serialization.insert_str(e, &rendered);
+        self.extensions_len += rendered.len();
+        self
+    }
+
+    fn replace_extensions(&mut self, rendered: &str) {
+        let s = self.max_age_end_or_prior();
+        let e = self.extensions_end_or_prior();
+        self.serialization.replace_range(s..e, rendered);
+        self.extensions_len = rendered.len();
+    }
+
     pub fn secure(&self) -> bool {
         self.secure
     }
 
     #[inline]
     fn secure_end_or_prior(&self) -> usize {
-        self.max_age_end_or_prior() + if self.secure { SECURE_FLAG.len() } else { 0 }
+        self.extensions_end_or_prior() + if self.secure { SECURE_FLAG.len() } else { 0 }
     }
 
     pub fn set_secure(&mut self, secure: bool) -> &mut Self {
         if self.secure != secure {
-            let preceding_end = self.max_age_end_or_prior();
+            let preceding_end = self.extensions_end_or_prior();
             let old_secure = self.secure;
             self.set_flag_str(preceding_end, SECURE_FLAG, old_secure, secure);
             self.secure = secure;
@@ -344,7 +587,85 @@ impl Cookie {
         self
     }
 
-    pub fn expires(&self) -> Option<Tm> {
+    pub fn partitioned(&self) -> bool {
+        self.partitioned
+    }
+
+    #[inline]
+    fn partitioned_end_or_prior(&self) -> usize {
+        self.httponly_end_or_prior() +
+        if self.partitioned {
+            PARTITIONED_FLAG.len()
+        } else {
+            0
+        }
+    }
+
+    pub fn set_partitioned(&mut self, partitioned: bool) -> &mut Self {
+        if self.partitioned != partitioned {
+            let preceding_end = self.httponly_end_or_prior();
+            let old_partitioned = self.partitioned;
+            self.set_flag_str(preceding_end, PARTITIONED_FLAG, old_partitioned, partitioned);
+            self.partitioned = partitioned;
+        }
+        self
+    }
+
+    pub fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    #[inline]
+    fn same_site_end_or_prior(&self) -> usize {
+        self.partitioned_end_or_prior() +
+        self.same_site.map_or(0, |s| SAME_SITE_PREFIX.len() + s.as_str().len())
+    }
+
+    pub fn set_same_site(&mut self, same_site: Option<SameSite>) -> &mut Self {
+        if self.same_site != same_site {
+            let preceding_end = self.partitioned_end_or_prior();
+            let old_end = self.same_site_end_or_prior();
+            let suffix = self.truncate_and_take(preceding_end, old_end);
+            if let Some(same_site) = same_site {
+                self.serialization.push_str(SAME_SITE_PREFIX);
+                self.serialization.push_str(same_site.as_str());
+            }
+            if let Some(ref s) = suffix {
+                self.serialization.push_str(s);
+            }
+            self.same_site = same_site;
+        }
+        self
+    }
+
+    pub fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
+
+    #[inline]
+    fn priority_end_or_prior(&self) -> usize {
+        self.same_site_end_or_prior() +
+        self.priority.map_or(0, |p| PRIORITY_PREFIX.len() + p.as_str().len())
+    }
+
+    pub fn set_priority(&mut self, priority: Option<Priority>) -> &mut Self {
+        if self.priority != priority {
+            let preceding_end = self.same_site_end_or_prior();
+            let old_end = self.priority_end_or_prior();
+            let suffix = self.truncate_and_take(preceding_end, old_end);
+            if let Some(priority) = priority {
+                self.serialization.push_str(PRIORITY_PREFIX);
+                self.serialization.push_str(priority.as_str());
+            }
+            if let Some(ref s) = suffix {
+                self.serialization.push_str(s);
+            }
+            self.priority = priority;
+        }
+        self
+    }
+
+    pub fn expires(&self) -> Option<Timestamp> {
         self.expires
     }
 
@@ -354,37 +675,62 @@ impl Cookie {
 
     #[inline]
     fn expires_value_start(&self) -> Option<usize> {
-        self.expires.map(|_| self.httponly_end_or_prior() + EXPIRES_PREFIX.len())
+        self.expires.map(|_| self.priority_end_or_prior() + EXPIRES_PREFIX.len())
+    }
+
+    // the instant the cookie expires when received at `now`, per RFC 6265 §5.3: Max-Age takes
+    // precedence over Expires and counts from receipt, with a non-positive Max-Age meaning
+    // the earliest representable time. None for a session cookie
+    pub fn expiry_at(&self, now: Timestamp) -> Option<Timestamp> {
+        match (self.max_age(), self.expires) {
+            (Some(delta), _) if delta <= 0 => Some(EARLIEST_TIMESTAMP),
+            (Some(delta), _) => Some(now.saturating_add_secs(delta)),
+            (None, expires) => expires,
+        }
+    }
+
+    // whether the cookie, received at `now`, is already expired; this is how servers delete
+    // cookies
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        self.expiry_at(now).is_some_and(|e| e <= now)
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        self.max_age.is_some() || self.expires.is_some()
+    }
+
+    pub fn is_session(&self) -> bool {
+        !self.is_persistent()
     }
 
     pub fn expire(&mut self) -> &mut Self {
         self.set_value("");
         self.set_max_age(0);
-        self.set_expires(Some(*EARLIEST_TM))
+        self.set_expires(Some(EARLIEST_TIMESTAMP))
     }
 
-    pub fn set_expires(&mut self, expires: Option<Tm>) -> &mut Self {
-        if self.expires.is_none() && expires.is_none() {
-            return self;
-        }
-        let expires_utc = expires.map(|e| e.to_utc());
-        if self.expires == expires_utc {
+    // Expires is kept as whole seconds, clamped to the range an IMF-fixdate can express, so
+    // that `expires` is always the instant `expires_str` parses back to. the time zone of
+    // whatever the timestamp was converted from is irrelevant; it is always written in GMT
+    pub fn set_expires(&mut self, expires: Option<Timestamp>) -> &mut Self {
+        let expires = expires.map(normalize_expires);
+        if self.expires == expires {
             return self;
         }
 
-        match expires_utc {
+        match expires {
             None => {
-                let trunc_from = self.httponly_end_or_prior();
+                let trunc_from = self.priority_end_or_prior();
                 self.serialization.truncate(trunc_from);
             }
-            Some(expires_utc) => {
+            Some(expires) => {
                 if self.expires.is_none() {
                     self.serialization.push_str(EXPIRES_PREFIX);
                 } else {
-                    let trunc_from = self.httponly_end_or_prior() + EXPIRES_PREFIX.len();
+                    let trunc_from = self.priority_end_or_prior() + EXPIRES_PREFIX.len();
                     self.serialization.truncate(trunc_from);
                 }
-                self.serialization.push_str(&format!("{}", expires_utc.rfc822()));
+                self.serialization.push_str(&expires.to_string());
             }
         }
 
@@ -392,6 +738,10 @@ impl Cookie {
         self
     }
 
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        validate::validate(self)
+    }
+
     #[inline]
     fn set_attr_value(&mut self,
                       attr_name: &str,
@@ -401,7 +751,7 @@ impl Cookie {
                       preceding_end: usize)
                       -> (Option<usize>, Option<String>) {
         match old_value_start {
-            Some(_) if 0 == new_value.len() => {
+            Some(_) if new_value.is_empty() => {
                 self.serialization.drain(preceding_end..old_value_end.unwrap());
                 (None, None)
             }
@@ -410,7 +760,7 @@ impl Cookie {
                 self.serialization.push_str(new_value);
                 (Some(self.serialization.len()), suffix)
             }
-            None if 0 == new_value.len() => (None, None),
+            None if new_value.is_empty() => (None, None),
             None => {
                 let suffix = self.take(preceding_end);
                 self.serialization.push_str(attr_name);
@@ -429,7 +779,7 @@ impl Cookie {
         let suffix = {
             let slice_from = preceding_end + if !old_value { 0 } else { flag_str.len() };
             let s = self.slice(slice_from..);
-            if 0 == s.len() {
+            if s.is_empty() {
                 None
             } else {
                 Some(s.to_owned())
@@ -455,7 +805,7 @@ impl Cookie {
     fn truncate_and_take(&mut self, truncate_from: usize, take_from: usize) -> Option<String> {
         let taken = {
             let s = self.slice(take_from..);
-            if 0 == s.len() {
+            if s.is_empty() {
                 None
             } else {
                 Some(s.to_owned())
@@ -474,6 +824,120 @@ impl Cookie {
     pub fn into_string(self) -> String {
         self.serialization
     }
+
+    // the Debug output with `value` in place of the value, shared with `Redacted`
+    fn fmt_debug(&self, f: &mut fmt::Formatter, value: &dyn fmt::Debug) -> fmt::Result {
+        f.debug_struct("Cookie")
+            .field("name", &self.name())
+            .field("value", value)
+            .field("domain", &self.domain())
+            .field("path", &self.path())
+            .field("max_age", &self.max_age())
+            .field("extensions", &self.extensions().collect::<Vec<_>>())
+            .field("secure", &self.secure)
+            .field("httponly", &self.httponly)
+            .field("partitioned", &self.partitioned)
+            .field("same_site", &self.same_site)
+            .field("priority", &self.priority)
+            .field("expires", &self.expires_str())
+            .finish()
+    }
+}
+
+#[inline]
+fn normalize_expires(expires: Timestamp) -> Timestamp {
+    Timestamp::from_unix(expires.unix_secs().clamp(MIN_EXPIRES_SECS, MAX_EXPIRES_SECS))
+}
+
+// the Domain as user agents compare it: case-insensitively, ignoring a leading '.'
+fn domain_key(domain: Option<&str>) -> Option<&str> {
+    domain.map(|d| d.strip_prefix('.').unwrap_or(d))
+}
+
+// cookies are equal when they mean the same thing to a user agent, rather than when their
+// serializations are byte-identical: Domain is compared as `domain_key` does, extension
+// names case-insensitively, and Expires by instant
+impl PartialEq for Cookie {
+    fn eq(&self, other: &Cookie) -> bool {
+        let domains_eq = match (domain_key(self.domain()), domain_key(other.domain())) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        self.cookie_pair() == other.cookie_pair() && domains_eq && self.path() == other.path() &&
+        self.max_age() == other.max_age() && self.secure == other.secure &&
+        self.httponly == other.httponly && self.partitioned == other.partitioned &&
+        self.same_site == other.same_site && self.priority == other.priority &&
+        self.expires == other.expires &&
+        self.extensions().count() == other.extensions().count() &&
+        self.extensions().zip(other.extensions()).all(|((an, av), (bn, bv))| {
+            an.eq_ignore_ascii_case(bn) && av == bv
+        })
+    }
+}
+
+impl Eq for Cookie {}
+
+impl Hash for Cookie {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        fn hash_lowercase<H: Hasher>(s: &str, state: &mut H) {
+            for b in s.bytes() {
+                state.write_u8(b.to_ascii_lowercase());
+            }
+            state.write_u8(0xff);
+        }
+
+        self.cookie_pair().hash(state);
+        match domain_key(self.domain()) {
+            Some(domain) => hash_lowercase(domain, state),
+            None => state.write_u8(0),
+        }
+        self.path().hash(state);
+        self.max_age().hash(state);
+        (self.secure, self.httponly, self.partitioned).hash(state);
+        self.same_site.hash(state);
+        self.priority.hash(state);
+        self.expires.hash(state);
+        for (name, value) in self.extensions() {
+            hash_lowercase(name, state);
+            value.hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_debug(f, &self.value())
+    }
+}
+
+// the Set-Cookie header value
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Cookie {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Cookie, ParseError> {
+        Cookie::parse(s)
+    }
+}
+
+fn render_extensions<'a, I>(extensions: I) -> String
+    where I: IntoIterator<Item = (&'a str, Option<&'a str>)>
+{
+    let mut s = String::new();
+    for (name, value) in extensions {
+        s.push_str("; ");
+        s.push_str(name);
+        if let Some(value) = value {
+            s.push('=');
+            s.push_str(value);
+        }
+    }
+    s
 }
 
 #[inline]
@@ -483,9 +947,9 @@ fn adjust(index: &mut usize, old: usize, new: usize) {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
-    use super::Cookie;
-    use time;
+    use super::{Cookie, Priority, SameSite, Timestamp, ValidationError};
     #[test]
     fn name_value() {
         let mut c = Cookie::new("foo", "bar");
@@ -496,12 +960,12 @@ mod tests {
         assert_eq!(c.cookie_pair(), ("foo", "bar"));
         assert_eq!(c.as_str(), "foo=bar; Secure");
 
-        c.set_name("quux".into());
+        c.set_name("quux");
         assert_eq!(c.name(), "quux");
         assert_eq!(c.value(), "bar");
         assert_eq!(c.cookie_pair(), ("quux", "bar"));
         assert_eq!(c.as_str(), "quux=bar; Secure");
-        c.set_name("  foo  ".into());
+        c.set_name("  foo  ");
         assert_eq!(c.name(), "foo");
         assert_eq!(c.value(), "bar");
         assert_eq!(c.cookie_pair(), ("foo", "bar"));
@@ -519,8 +983,7 @@ mod tests {
 
     #[test]
     fn field_combos() {
-        let expires = "Thu, 22 Mar 2012 14:53:18 GMT";
-        let tm = time::strptime(expires, "%a, %d %b %Y %T GMT").unwrap();
+        let tm = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
 
         let mut c = Cookie::new("foo", "bar");
         c.set_domain("www.example.com");
@@ -712,7 +1175,7 @@ mod tests {
         assert_eq!(c.expires_str(), None);
         assert_eq!(c.secure(), false);
         assert_eq!(c.as_str(), "foo=bar; Max-Age=60; HttpOnly");
-        c.set_max_age(0);
+        c.clear_max_age();
         assert_eq!(c.name(), "foo");
         assert_eq!(c.value(), "bar");
         assert_eq!(c.domain(), None);
@@ -741,7 +1204,7 @@ mod tests {
     #[test]
     fn expires() {
         let expires = "Thu, 22 Mar 2012 14:53:18 GMT";
-        let tm = time::strptime(expires, "%a, %d %b %Y %T GMT").unwrap();
+        let tm = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
         let mut c = Cookie::new("foo", "bar");
         assert_eq!(c.as_str(), "foo=bar");
         assert_eq!(c.expires(), None);
@@ -759,8 +1222,289 @@ mod tests {
         c.set_expires(None);
         assert_eq!(c.as_str(), "foo=bar; Domain=www.example.com");
         c.expire();
+        assert_eq!(c.max_age(), Some(0));
+        assert_eq!(c.as_str(),
+                   "foo=; Domain=www.example.com; Max-Age=0; \
+                    Expires=Mon, 01 Jan 1900 00:00:00 GMT");
+    }
+
+    #[test]
+    fn traits() {
+        use std::collections::HashSet;
+
+        let c: Cookie = "foo=bar; Domain=Example.COM; Path=/; SameParty; Expires=Thu, 22 Mar \
+                         2012 14:53:18 GMT"
+            .parse()
+            .unwrap();
+        assert_eq!(c.to_string(), c.as_str());
+        assert_eq!(c.clone().as_str(), c.as_str());
+        assert_eq!(format!("{:?}", c),
+                   "Cookie { name: \"foo\", value: \"bar\", domain: Some(\"Example.COM\"), \
+                    path: Some(\"/\"), max_age: None, extensions: [(\"SameParty\", None)], \
+                    secure: false, httponly: false, partitioned: false, same_site: None, \
+                    priority: None, expires: Some(\"Thu, 22 Mar 2012 14:53:18 GMT\") }");
+
+        // equal despite differently written Domain, extension and Expires
+        let d: Cookie = "foo=bar; path=/; sameparty; expires=Thursday, 22-Mar-12 14:53:18 UTC; \
+                         domain=.example.com"
+            .parse()
+            .unwrap();
+        assert!(c.as_str() != d.as_str());
+        assert_eq!(c, d);
+        let mut set = HashSet::new();
+        set.insert(c.clone());
+        assert!(set.contains(&d));
+
+        let mut e = d.clone();
+        e.set_secure(true);
+        assert!(c != e);
+        e.set_secure(false).set_value("baz");
+        assert!(c != e);
+        e.set_value("bar").set_extension("SameParty", Some("1"));
+        assert!(c != e);
+        e.set_extension("SameParty", None).set_domain("www.example.com");
+        assert!(c != e);
+        e.set_domain("EXAMPLE.com");
+        assert_eq!(c, e);
+        set.insert(e);
+        assert_eq!(set.len(), 1);
+
+        assert!("foo".parse::<Cookie>().is_err());
+    }
+
+    #[test]
+    fn expiry() {
+        let now = Timestamp::from_utc(2020, 1, 1, 0, 0, 0).unwrap();
+        let past = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let future = Timestamp::from_utc(2030, 1, 1, 0, 0, 0).unwrap();
+
+        let mut c = Cookie::new("foo", "bar");
+        assert!(c.is_session() && !c.is_persistent());
+        assert_eq!(c.expiry_at(now), None);
+        assert!(!c.is_expired(now));
+
+        c.set_expires(Some(future));
+        assert!(c.is_persistent() && !c.is_session());
+        assert_eq!(c.expiry_at(now), Some(future));
+        assert!(!c.is_expired(now));
+        assert!(c.is_expired(future));
+
+        // Max-Age wins over Expires, in either direction
+        c.set_max_age(60);
+        assert_eq!(c.expiry_at(now), Some(now.saturating_add_secs(60)));
+        c.set_expires(Some(past));
+        assert!(!c.is_expired(now));
+        c.set_max_age(0);
+        assert!(c.is_expired(now));
+        c.set_max_age(-1).set_expires(Some(future));
+        assert!(c.is_expired(now));
+        assert_eq!(c.expiry_at(now), Timestamp::from_utc(1900, 1, 1, 0, 0, 0));
+
+        c.clear_max_age();
+        assert_eq!(c.expiry_at(now), Some(future));
+        c.set_expires(None);
+        assert!(c.is_session());
+
+        let mut c = Cookie::new("foo", "bar");
+        c.expire();
+        assert!(c.is_expired(now));
+    }
+
+    #[test]
+    fn expires_normalized() {
+        use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+        // sub-second precision is dropped, so the value and the string agree
+        let mut c = Cookie::new("foo", "bar");
+        let t = UNIX_EPOCH + Duration::new(1_332_427_998, 999_999_999);
+        c.set_expires(Some(t.into()));
+        assert_eq!(c.expires_str(), Some("Thu, 22 Mar 2012 14:53:18 GMT"));
+        assert_eq!(c.expires(), Timestamp::from_utc(2012, 3, 22, 14, 53, 18));
+        assert_eq!(c.expires(), ::parse_cookie_date(c.expires_str().unwrap()));
+        assert_eq!(SystemTime::from(c.expires().unwrap()),
+                   UNIX_EPOCH + Duration::from_secs(1_332_427_998));
+
+        // and Expires outside the four-digit years is clamped
+        c.set_expires(Some(Timestamp::from_unix(i64::MAX)));
+        assert_eq!(c.expires_str(), Some("Fri, 31 Dec 9999 23:59:59 GMT"));
+        assert_eq!(c.expires(), ::parse_cookie_date(c.expires_str().unwrap()));
+        c.set_expires(Some(Timestamp::from_unix(i64::MIN)));
+        assert_eq!(c.expires_str(), Some("Mon, 01 Jan 1601 00:00:00 GMT"));
+        assert_eq!(c.expires(), ::parse_cookie_date(c.expires_str().unwrap()));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn expires_time_offset() {
+        use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
+
+        let local = PrimitiveDateTime::new(Date::from_calendar_date(2012, Month::March, 22)
+                                               .unwrap(),
+                                           Time::from_hms_milli(9, 53, 18, 500).unwrap())
+            .assume_offset(UtcOffset::from_hms(-5, 0, 0).unwrap());
+        let mut c = Cookie::new("foo", "bar");
+        c.set_expires(Some(local.into()));
+        assert_eq!(c.as_str(), "foo=bar; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        assert_eq!(c.expires(), Timestamp::from_utc(2012, 3, 22, 14, 53, 18));
+
+        // the same instant in another offset is no change
+        let tokyo = local.to_offset(UtcOffset::from_hms(9, 0, 0).unwrap());
+        c.set_expires(Some(tokyo.into()));
+        assert_eq!(c.as_str(), "foo=bar; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn expires_chrono_offset() {
+        use chrono::{FixedOffset, TimeZone};
+
+        let local = FixedOffset::west_opt(8 * 3600)
+            .unwrap()
+            .with_ymd_and_hms(2012, 3, 22, 23, 53, 18)
+            .unwrap();
+        let mut c = Cookie::new("foo", "bar");
+        c.set_expires(Some(local.into()));
+        assert_eq!(c.as_str(), "foo=bar; Expires=Fri, 23 Mar 2012 07:53:18 GMT");
+        assert_eq!(c.expires(), Timestamp::from_utc(2012, 3, 23, 7, 53, 18));
+    }
+
+    #[test]
+    fn max_age() {
+        let mut c = Cookie::new("foo", "bar");
+        c.set_max_age(0);
+        assert_eq!(c.max_age(), Some(0));
+        assert_eq!(c.max_age_str(), Some("0"));
+        assert_eq!(c.as_str(), "foo=bar; Max-Age=0");
+        c.set_secure(true);
+        c.set_max_age(-30);
+        assert_eq!(c.max_age(), Some(-30));
+        assert_eq!(c.max_age_str(), Some("-30"));
+        assert_eq!(c.as_str(), "foo=bar; Max-Age=-30; Secure");
+        c.set_max_age(3600);
+        assert_eq!(c.as_str(), "foo=bar; Max-Age=3600; Secure");
+        c.clear_max_age();
+        assert_eq!(c.max_age(), None);
+        assert_eq!(c.max_age_str(), None);
+        assert_eq!(c.as_str(), "foo=bar; Secure");
+        c.clear_max_age();
+        assert_eq!(c.as_str(), "foo=bar; Secure");
+    }
+
+    #[test]
+    fn same_site() {
+        let expires = "Thu, 22 Mar 2012 14:53:18 GMT";
+        let tm = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let mut c = Cookie::new("foo", "bar");
+        c.set_same_site(Some(SameSite::Lax));
+        assert_eq!(c.same_site(), Some(SameSite::Lax));
+        assert_eq!(c.as_str(), "foo=bar; SameSite=Lax");
+        c.set_expires(Some(tm));
+        c.set_httponly(true);
+        assert_eq!(c.as_str(),
+                   "foo=bar; HttpOnly; SameSite=Lax; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        c.set_same_site(Some(SameSite::Strict));
+        assert_eq!(c.same_site(), Some(SameSite::Strict));
+        assert_eq!(c.expires_str(), Some(expires));
+        assert_eq!(c.as_str(),
+                   "foo=bar; HttpOnly; SameSite=Strict; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        c.set_path("/").set_secure(true);
+        assert_eq!(c.as_str(),
+                   "foo=bar; Path=/; Secure; HttpOnly; SameSite=Strict; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        c.set_same_site(None);
+        assert_eq!(c.same_site(), None);
+        assert_eq!(c.expires(), Some(tm));
+        assert_eq!(c.as_str(),
+                   "foo=bar; Path=/; Secure; HttpOnly; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        c.set_same_site(Some(SameSite::None));
+        c.set_expires(None);
+        assert_eq!(c.as_str(), "foo=bar; Path=/; Secure; HttpOnly; SameSite=None");
+    }
+
+    #[test]
+    fn partitioned() {
+        let expires = "Thu, 22 Mar 2012 14:53:18 GMT";
+        let tm = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let mut c = Cookie::new("foo", "bar");
+        c.set_partitioned(true);
+        assert_eq!(c.partitioned(), true);
+        assert_eq!(c.as_str(), "foo=bar; Partitioned");
+        c.set_same_site(Some(SameSite::None)).set_expires(Some(tm));
+        c.set_secure(true).set_httponly(true);
+        assert_eq!(c.as_str(),
+                   "foo=bar; Secure; HttpOnly; Partitioned; SameSite=None; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        c.set_httponly(false);
+        assert_eq!(c.as_str(),
+                   "foo=bar; Secure; Partitioned; SameSite=None; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        c.set_partitioned(false);
+        assert_eq!(c.partitioned(), false);
+        assert_eq!(c.same_site(), Some(SameSite::None));
+        assert_eq!(c.expires_str(), Some(expires));
+        assert_eq!(c.as_str(),
+                   "foo=bar; Secure; SameSite=None; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+    }
+
+    #[test]
+    fn priority() {
+        let expires = "Thu, 22 Mar 2012 14:53:18 GMT";
+        let tm = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let mut c = Cookie::new("foo", "bar");
+        c.set_priority(Some(Priority::High));
+        assert_eq!(c.priority(), Some(Priority::High));
+        assert_eq!(c.as_str(), "foo=bar; Priority=High");
+        c.set_expires(Some(tm)).set_same_site(Some(SameSite::Strict)).set_secure(true);
+        assert_eq!(c.as_str(),
+                   "foo=bar; Secure; SameSite=Strict; Priority=High; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+        c.set_priority(Some(Priority::Low));
+        assert_eq!(c.expires_str(), Some(expires));
+        c.set_expires(None);
+        assert_eq!(c.as_str(), "foo=bar; Secure; SameSite=Strict; Priority=Low");
+        c.set_priority(None).set_same_site(None);
+        assert_eq!(c.priority(), None);
+        assert_eq!(c.as_str(), "foo=bar; Secure");
+
+        assert!(Priority::Low < Priority::Medium && Priority::Medium < Priority::High);
+    }
+
+    #[test]
+    fn extensions() {
+        let mut c = Cookie::new("foo", "bar");
+        c.set_secure(true).set_same_site(Some(SameSite::Lax));
+        c.set_extension("SameParty", None).set_extension(" X-Tier ", Some(" Low "));
+        assert_eq!(c.as_str(), "foo=bar; SameParty; X-Tier=Low; Secure; SameSite=Lax");
+        assert_eq!(c.extension("x-tier"), Some(Some("Low")));
+        assert_eq!(c.extension("sameparty"), Some(None));
+        assert_eq!(c.extension("X"), None);
+
+        // replaced in place, keeping the position among the others
+        c.set_extension("X-TIER", Some("High"));
+        c.set_path("/").set_max_age(60);
         assert_eq!(c.as_str(),
-                   "foo=; Domain=www.example.com; Expires=Sun, 01 Jan 1900 00:00:00 GMT");
+                   "foo=bar; Path=/; Max-Age=60; SameParty; X-TIER=High; Secure; SameSite=Lax");
+        assert_eq!(c.extensions().collect::<Vec<_>>(),
+                   vec![("SameParty", None), ("X-TIER", Some("High"))]);
+
+        // attributes with their own setters are not extensions
+        c.set_extension("secure", None).set_extension("Path", Some("/x"));
+        assert_eq!(c.path(), Some("/"));
+        assert_eq!(c.try_set_extension("Domain", Some("a")).err(),
+                   Some(ValidationError::InvalidExtension));
+        assert_eq!(c.try_set_extension("X", Some("a;b")).err(),
+                   Some(ValidationError::InvalidExtension));
+
+        c.remove_extension("sameparty").set_secure(false);
+        assert_eq!(c.as_str(), "foo=bar; Path=/; Max-Age=60; X-TIER=High; SameSite=Lax");
+        c.remove_extension("X-Tier").remove_extension("X");
+        assert_eq!(c.as_str(), "foo=bar; Path=/; Max-Age=60; SameSite=Lax");
+        assert_eq!(c.extensions().next(), None);
+
+        // a proxy can change one field and pass the rest through
+        let mut c = Cookie::parse("sid=abc; Path=/; SameParty; Secure; X-Trace=1").unwrap();
+        c.set_value("def");
+        assert_eq!(c.as_str(), "sid=def; Path=/; SameParty; X-Trace=1; Secure");
     }
 
     #[test]
@@ -0,0 +1,325 @@
+// the Netscape cookies.txt format read and written by curl, wget and browser extensions. each
+// line is a cookie with seven tab-separated fields: domain, include-subdomains flag, path,
+// secure flag, expiry as unix seconds (0 for a session cookie), name and value. the flags are
+// `TRUE` or `FALSE`; a domain prefixed with `#HttpOnly_` is an HttpOnly cookie, and other
+// lines starting with '#' are comments
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use jar::StoredCookie;
+use validate;
+use {CookieBuilder, CookieJar, Timestamp};
+
+const HEADER: &str = "# Netscape HTTP Cookie File";
+const HTTPONLY_PREFIX: &str = "#HttpOnly_";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MalformedLine {
+    // not seven tab-separated fields, or six with the value left off
+    FieldCount,
+    // the domain field is empty
+    EmptyDomain,
+    // a flag field is neither TRUE nor FALSE
+    InvalidFlag,
+    // the expiry is not an integer
+    InvalidExpiry,
+    // the name is empty or contains '=' or ';', or the value contains ';'
+    InvalidPair,
+}
+
+impl fmt::Display for MalformedLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MalformedLine::FieldCount => write!(f, "expected 7 tab-separated fields"),
+            MalformedLine::EmptyDomain => write!(f, "domain is empty"),
+            MalformedLine::InvalidFlag => write!(f, "flag is neither TRUE nor FALSE"),
+            MalformedLine::InvalidExpiry => write!(f, "expiry is not an integer"),
+            MalformedLine::InvalidPair => write!(f, "name or value contains invalid characters"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum NetscapeError {
+    Io(io::Error),
+    // a line which is not a cookie, by its 1-based line number
+    Malformed(usize, MalformedLine),
+}
+
+impl NetscapeError {
+    // the line number of a malformed line
+    pub fn line(&self) -> Option<usize> {
+        match *self {
+            NetscapeError::Io(_) => None,
+            NetscapeError::Malformed(line, _) => Some(line),
+        }
+    }
+}
+
+impl fmt::Display for NetscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NetscapeError::Io(ref e) => e.fmt(f),
+            NetscapeError::Malformed(line, ref e) => write!(f, "line {}: {}", line, e),
+        }
+    }
+}
+
+impl Error for NetscapeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            NetscapeError::Io(ref e) => Some(e),
+            NetscapeError::Malformed(..) => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetscapeError {
+    fn from(e: io::Error) -> NetscapeError {
+        NetscapeError::Io(e)
+    }
+}
+
+impl CookieJar {
+    // adds the cookies of a cookies.txt file, replacing those with the same name, domain and
+    // path. nothing is added unless every line is well-formed; cookies already expired are
+    // skipped
+    pub fn read_netscape<R: BufRead>(&mut self, reader: R) -> Result<(), NetscapeError> {
+        self.read_netscape_at(reader, Timestamp::now())
+    }
+
+    fn read_netscape_at<R: BufRead>(&mut self,
+                                    reader: R,
+                                    now: Timestamp)
+                                    -> Result<(), NetscapeError> {
+        let mut cookies = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if let Some(stored) = parse_line(&line, now)
+                .map_err(|e| NetscapeError::Malformed(i + 1, e))? {
+                cookies.push(stored);
+            }
+        }
+        for stored in cookies {
+            self.insert_at(stored, now);
+        }
+        Ok(())
+    }
+
+    // writes the jar as a cookies.txt file. Partitioned cookies are left out, since the
+    // format has no place for the partition and they would be read back as unpartitioned, as
+    // are cookies with a tab or line break in a field, which would split it, and cookies which
+    // have expired but not yet been removed
+    pub fn write_netscape<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let now = Timestamp::now();
+        writeln!(writer, "{}", HEADER)?;
+        for stored in self.iter() {
+            let cookie = stored.cookie();
+            let fields = [stored.domain(), stored.path(), cookie.name(), cookie.value()];
+            if stored.partition_key().is_some() || stored.is_expired(now) ||
+               fields.iter().any(|f| f.contains(['\t', '\r', '\n'])) {
+                continue;
+            }
+            writeln!(writer,
+                     "{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                     if cookie.httponly() { HTTPONLY_PREFIX } else { "" },
+                     if stored.host_only() { "" } else { "." },
+                     stored.domain(),
+                     flag(!stored.host_only()),
+                     stored.path(),
+                     flag(cookie.secure()),
+                     stored.expiry_time().map_or(0, |t| t.unix_secs()),
+                     cookie.name(),
+                     cookie.value())?;
+        }
+        Ok(())
+    }
+}
+
+// the cookie on a line, or None for a blank line, a comment or an expired cookie
+fn parse_line(line: &str, now: Timestamp) -> Result<Option<StoredCookie>, MalformedLine> {
+    let (line, httponly) = match line.strip_prefix(HTTPONLY_PREFIX) {
+        Some(rest) => (rest, true),
+        None if line.trim().is_empty() || line.starts_with('#') => return Ok(None),
+        None => (line, false),
+    };
+
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() != 7 && fields.len() != 6 {
+        return Err(MalformedLine::FieldCount);
+    }
+    let domain = fields[0].trim();
+    let domain = domain.strip_prefix('.').unwrap_or(domain).to_lowercase();
+    if domain.is_empty() {
+        return Err(MalformedLine::EmptyDomain);
+    }
+    let host_only = !parse_flag(fields[1])?;
+    let path = match fields[2] {
+        "" => "/",
+        path => path,
+    };
+    let secure = parse_flag(fields[3])?;
+    let expiry = fields[4].trim().parse::<i64>().map_err(|_| MalformedLine::InvalidExpiry)?;
+    let name = fields[5];
+    let value = fields.get(6).cloned().unwrap_or("");
+    if !validate::is_storable_pair(name, value) {
+        return Err(MalformedLine::InvalidPair);
+    }
+
+    let expiry_time = match expiry {
+        0 => None,
+        secs => Some(Timestamp::from_unix(secs)),
+    };
+    if expiry_time.is_some_and(|t| t <= now) {
+        return Ok(None);
+    }
+    let mut builder = CookieBuilder::new(name, value)
+        .path(path)
+        .secure(secure)
+        .httponly(httponly)
+        .expires(expiry_time);
+    if !host_only {
+        builder = builder.domain(&domain[..]);
+    }
+    let cookie = builder.build();
+    Ok(Some(StoredCookie::restored(cookie,
+                                   domain,
+                                   path.to_owned(),
+                                   host_only,
+                                   expiry_time,
+                                   now)))
+}
+
+fn parse_flag(field: &str) -> Result<bool, MalformedLine> {
+    let field = field.trim();
+    if field.eq_ignore_ascii_case("TRUE") {
+        Ok(true)
+    } else if field.eq_ignore_ascii_case("FALSE") {
+        Ok(false)
+    } else {
+        Err(MalformedLine::InvalidFlag)
+    }
+}
+
+fn flag(b: bool) -> &'static str {
+    if b { "TRUE" } else { "FALSE" }
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{MalformedLine, NetscapeError};
+    use {Cookie, CookieJar, Timestamp};
+
+    const FILE: &str = "# Netscape HTTP Cookie File\n\
+                        # https://curl.se/docs/http-cookies.html\n\
+                        \n\
+                        .example.com\tTRUE\t/\tFALSE\t0\ttheme\tdark\n\
+                        #HttpOnly_www.example.com\tFALSE\t/account\tTRUE\t2000000000\tsid\tabc\r\n\
+                        example.org\tFALSE\t/\tFALSE\t1000\told\tgone\n\
+                        example.org\tfalse\t\tfalse\t2000000000\tempty\n";
+
+    #[test]
+    fn read() {
+        let mut jar = CookieJar::new();
+        jar.read_netscape_at(FILE.as_bytes(), Timestamp::from_unix(1_500_000_000)).unwrap();
+        assert_eq!(jar.len(), 3);
+
+        let theme = jar.iter().find(|c| c.cookie().name() == "theme").unwrap();
+        assert_eq!(theme.domain(), "example.com");
+        assert!(!theme.host_only());
+        assert!(!theme.persistent());
+        assert_eq!(theme.cookie().as_str(), "theme=dark; Domain=example.com; Path=/");
+
+        let sid = jar.iter().find(|c| c.cookie().name() == "sid").unwrap();
+        assert_eq!(sid.domain(), "www.example.com");
+        assert!(sid.host_only());
+        assert_eq!(sid.path(), "/account");
+        assert_eq!(sid.expiry_time(), Some(Timestamp::from_unix(2_000_000_000)));
+        assert_eq!(sid.cookie().as_str(),
+                   "sid=abc; Path=/account; Secure; HttpOnly; \
+                    Expires=Wed, 18 May 2033 03:33:20 GMT");
+
+        let empty = jar.iter().find(|c| c.cookie().name() == "empty").unwrap();
+        assert_eq!(empty.cookie().value(), "");
+        assert_eq!(empty.path(), "/");
+
+        let url = Url::parse("https://www.example.com/account/").unwrap();
+        assert_eq!(jar.request_header(&url), Some("sid=abc; theme=dark".to_owned()));
+    }
+
+    #[test]
+    fn malformed() {
+        let read = |s: &str| CookieJar::new().read_netscape(s.as_bytes());
+        let malformed = |s: &str| match read(s) {
+            Err(NetscapeError::Malformed(line, e)) => Some((line, e)),
+            _ => None,
+        };
+
+        assert_eq!(malformed("# comment\n\na.com\tTRUE\t/\n"),
+                   Some((3, MalformedLine::FieldCount)));
+        assert_eq!(malformed("a.com\tYES\t/\tFALSE\t0\ta\t1\n"),
+                   Some((1, MalformedLine::InvalidFlag)));
+        assert_eq!(malformed("a.com\tTRUE\t/\tFALSE\tnever\ta\t1\n"),
+                   Some((1, MalformedLine::InvalidExpiry)));
+        assert_eq!(malformed("\tTRUE\t/\tFALSE\t0\ta\t1\n"),
+                   Some((1, MalformedLine::EmptyDomain)));
+        assert_eq!(malformed("a.com\tTRUE\t/\tFALSE\t0\t\t1\n"),
+                   Some((1, MalformedLine::InvalidPair)));
+        assert_eq!(malformed("a.com\tTRUE\t/\tFALSE\t0\ta\t1;2\n"),
+                   Some((1, MalformedLine::InvalidPair)));
+        assert!(read(" \n# only comments\n").is_ok());
+
+        let e = read("a.com\tTRUE\t/\tFALSE\t0\ta\t1\nb.com\tTRUE\n").unwrap_err();
+        assert_eq!(e.line(), Some(2));
+        assert_eq!(e.to_string(), "line 2: expected 7 tab-separated fields");
+
+        // nothing is added from a file with a malformed line
+        let mut jar = CookieJar::new();
+        assert!(jar.read_netscape("a.com\tTRUE\t/\tFALSE\t0\ta\t1\nbad\n".as_bytes()).is_err());
+        assert!(jar.is_empty());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut jar = CookieJar::new();
+        let request = Url::parse("https://www.example.com/").unwrap();
+        let top_level = Url::parse("https://example.org/").unwrap();
+        jar.store(Cookie::parse("host=1; Path=/; HttpOnly").unwrap(), &request).unwrap();
+        jar.store(Cookie::parse("dom=2; Domain=example.com; Path=/a; Secure; \
+                                 Expires=Wed, 18 May 2033 03:33:20 GMT")
+                      .unwrap(),
+                  &request)
+            .unwrap();
+        jar.store_partitioned(Cookie::parse("chip=3; Secure; Partitioned").unwrap(),
+                              &request,
+                              &top_level)
+            .unwrap();
+        jar.store(Cookie::parse("tab=x\ty; Path=/").unwrap(), &request).unwrap();
+        assert_eq!(jar.len(), 4);
+
+        let mut out = Vec::new();
+        jar.write_netscape(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out,
+                   "# Netscape HTTP Cookie File\n\
+                    #HttpOnly_www.example.com\tFALSE\t/\tFALSE\t0\thost\t1\n\
+                    .example.com\tTRUE\t/a\tTRUE\t2000000000\tdom\t2\n");
+
+        let mut back = CookieJar::new();
+        back.read_netscape(out.as_bytes()).unwrap();
+        assert_eq!(back.len(), 2);
+        for stored in back.iter() {
+            let name = stored.cookie().name();
+            let original = jar.iter().find(|c| c.cookie().name() == name).unwrap();
+            assert_eq!(stored.domain(), original.domain());
+            assert_eq!(stored.host_only(), original.host_only());
+            assert_eq!(stored.path(), original.path());
+            assert_eq!(stored.expiry_time(), original.expiry_time());
+            assert_eq!(stored.cookie(), original.cookie());
+        }
+    }
+}
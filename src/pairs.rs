@@ -0,0 +1,91 @@
+use std::str::Split;
+
+use Cookie;
+
+// iterates the cookie-pairs of a request Cookie header (RFC 6265 §4.2, §5.4), in the order
+// sent. empty segments and segments lacking a name are skipped, surrounding whitespace is
+// trimmed, and a DQUOTE-wrapped value is unwrapped. duplicate names are all yielded; user
+// agents send the most specific cookie first
+pub struct CookiePairs<'a> {
+    segments: Split<'a, char>,
+}
+
+impl<'a> Iterator for CookiePairs<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<(&'a str, &'a str)> {
+        for segment in &mut self.segments {
+            let (name, value) = match segment.find('=') {
+                Some(i) => (trim_wsp(&segment[..i]), trim_wsp(&segment[i + 1..])),
+                None => continue,
+            };
+            if name.is_empty() {
+                continue;
+            }
+            let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+                &value[1..value.len() - 1]
+            } else {
+                value
+            };
+            return Some((name, value));
+        }
+        None
+    }
+}
+
+pub fn parse_cookie_header(header: &str) -> CookiePairs<'_> {
+    CookiePairs { segments: header.split(';') }
+}
+
+// builds a request Cookie header from the cookie-pairs of the given cookies, in order
+pub fn cookie_header<'a, I>(cookies: I) -> String
+    where I: IntoIterator<Item = &'a Cookie>
+{
+    let mut header = String::new();
+    for cookie in cookies {
+        let (name, value) = cookie.cookie_pair();
+        if !header.is_empty() {
+            header.push_str("; ");
+        }
+        header.push_str(name);
+        header.push('=');
+        header.push_str(value);
+    }
+    header
+}
+
+#[inline]
+fn trim_wsp(s: &str) -> &str {
+    s.trim_matches(|c| c == ' ' || c == '\t')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cookie_header, parse_cookie_header};
+    use Cookie;
+
+    #[test]
+    fn pairs() {
+        let pairs: Vec<_> = parse_cookie_header("a=1; b=2").collect();
+        assert_eq!(pairs, vec![("a", "1"), ("b", "2")]);
+
+        let pairs: Vec<_> = parse_cookie_header("  a = 1 ;;b=2;  ; c=;=4; d ; e=\"5\"; f=\"")
+            .collect();
+        assert_eq!(pairs, vec![("a", "1"), ("b", "2"), ("c", ""), ("e", "5"), ("f", "\"")]);
+
+        let pairs: Vec<_> = parse_cookie_header("a=1; a=2; b=x=y").collect();
+        assert_eq!(pairs, vec![("a", "1"), ("a", "2"), ("b", "x=y")]);
+
+        assert_eq!(parse_cookie_header("").next(), None);
+    }
+
+    #[test]
+    fn header() {
+        let mut a = Cookie::new("a", "1");
+        a.set_path("/").set_secure(true);
+        let b = Cookie::new("b", "2");
+        assert_eq!(cookie_header(vec![&a, &b]), "a=1; b=2");
+        assert_eq!(cookie_header(&[a, b]), "a=1; b=2");
+        assert_eq!(cookie_header(&[]), "");
+    }
+}
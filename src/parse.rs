@@ -0,0 +1,487 @@
+use std::error::Error;
+use std::fmt;
+
+use std::ops::Range;
+
+use timestamp::MONTHS;
+use {CookieRef, Priority, RangeArg, SameSite, Timestamp};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    // the name-value-pair contained no '='
+    MissingPair,
+    // the name-value-pair had an empty name
+    EmptyName,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::MissingPair => write!(f, "cookie name-value-pair is missing '='"),
+            ParseError::EmptyName => write!(f, "cookie name is empty"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+// implements the user agent algorithm of RFC 6265 §5.2; for recognized attributes the last
+// occurrence wins, while unrecognized attributes are kept as extensions
+pub fn parse(s: &str) -> Result<CookieRef<'_>, ParseError> {
+    let pair_end = s.find(';').unwrap_or(s.len());
+    let eq = s[..pair_end].find('=').ok_or(ParseError::MissingPair)?;
+    let name = trim_wsp(s, 0..eq);
+    let value = trim_wsp(s, eq + 1..pair_end);
+    if name.start == name.end {
+        return Err(ParseError::EmptyName);
+    }
+
+    let mut cookie = CookieRef {
+        source: s,
+        name,
+        value,
+        domain: None,
+        path: None,
+        max_age: None,
+        secure: false,
+        httponly: false,
+        partitioned: false,
+        same_site: None,
+        priority: None,
+        expires: None,
+    };
+
+    for (av_name, av_value) in Attributes::new(s) {
+        let av_name = av_name.slice_of(s);
+        let av_value = av_value.unwrap_or(s.len()..s.len());
+        let value_str = av_value.clone().slice_of(s);
+
+        if av_name.eq_ignore_ascii_case("Expires") {
+            if let Some(tm) = parse_cookie_date(value_str) {
+                cookie.expires = Some((tm, av_value));
+            }
+        } else if av_name.eq_ignore_ascii_case("Max-Age") {
+            if let Some(delta) = parse_max_age(value_str) {
+                cookie.max_age = Some((delta, av_value));
+            }
+        } else if av_name.eq_ignore_ascii_case("Domain") {
+            // an empty Domain is ignored entirely, per the SHOULD of §5.2.3, as is one which
+            // is empty once its leading '.' is dropped
+            let skip = if value_str.starts_with('.') { 1 } else { 0 };
+            if value_str.len() > skip {
+                cookie.domain = Some(av_value.start + skip..av_value.end);
+            }
+        } else if av_name.eq_ignore_ascii_case("Path") {
+            // an invalid Path means the default-path, which can only be computed by whoever
+            // knows the request-uri, so leave it unset
+            cookie.path = if value_str.starts_with('/') {
+                Some(av_value)
+            } else {
+                None
+            };
+        } else if av_name.eq_ignore_ascii_case("Secure") {
+            cookie.secure = true;
+        } else if av_name.eq_ignore_ascii_case("HttpOnly") {
+            cookie.httponly = true;
+        } else if av_name.eq_ignore_ascii_case("Partitioned") {
+            cookie.partitioned = true;
+        } else if av_name.eq_ignore_ascii_case("SameSite") {
+            // unrecognized enforcement values leave the attribute unset, as in RFC 6265bis
+            cookie.same_site = parse_same_site(value_str);
+        } else if av_name.eq_ignore_ascii_case("Priority") {
+            cookie.priority = parse_priority(value_str);
+        }
+    }
+
+    Ok(cookie)
+}
+
+const KNOWN_ATTRIBUTES: [&str; 9] = ["Expires",
+                                      "Max-Age",
+                                      "Domain",
+                                      "Path",
+                                      "Secure",
+                                      "HttpOnly",
+                                      "Partitioned",
+                                      "SameSite",
+                                      "Priority"];
+
+pub fn is_known_attribute(name: &str) -> bool {
+    KNOWN_ATTRIBUTES.iter().any(|a| a.eq_ignore_ascii_case(name))
+}
+
+// the cookie-avs following the name-value-pair, as trimmed (name, value) ranges; the value
+// is None for an attribute without '='
+pub struct Attributes<'a> {
+    s: &'a str,
+    av_end: usize,
+}
+
+impl<'a> Attributes<'a> {
+    pub fn new(s: &'a str) -> Attributes<'a> {
+        Attributes {
+            s,
+            av_end: s.find(';').unwrap_or(s.len()),
+        }
+    }
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = (Range<usize>, Option<Range<usize>>);
+
+    fn next(&mut self) -> Option<(Range<usize>, Option<Range<usize>>)> {
+        let s = self.s;
+        if self.av_end >= s.len() {
+            return None;
+        }
+        let av_start = self.av_end + 1;
+        let av_end = s[av_start..].find(';').map_or(s.len(), |i| av_start + i);
+        self.av_end = av_end;
+        Some(match s[av_start..av_end].find('=') {
+            Some(i) => {
+                (trim_wsp(s, av_start..av_start + i), Some(trim_wsp(s, av_start + i + 1..av_end)))
+            }
+            None => (trim_wsp(s, av_start..av_end), None),
+        })
+    }
+}
+
+// the unrecognized attributes of a cookie, as (name, value) pairs in the order they appear
+pub struct Extensions<'a> {
+    source: &'a str,
+    attributes: Attributes<'a>,
+}
+
+impl<'a> Extensions<'a> {
+    pub(crate) fn new(source: &'a str) -> Extensions<'a> {
+        Extensions {
+            source,
+            attributes: Attributes::new(source),
+        }
+    }
+}
+
+impl<'a> Iterator for Extensions<'a> {
+    type Item = (&'a str, Option<&'a str>);
+
+    fn next(&mut self) -> Option<(&'a str, Option<&'a str>)> {
+        let source = self.source;
+        self.attributes
+            .by_ref()
+            .map(|(name, value)| (name.slice_of(source), value.map(|v| v.slice_of(source))))
+            .find(|&(name, _)| !name.is_empty() && !is_known_attribute(name))
+    }
+}
+
+#[inline]
+fn trim_wsp(s: &str, range: Range<usize>) -> Range<usize> {
+    let is_wsp = |c| c == ' ' || c == '\t';
+    let slice = &s[range.clone()];
+    let start = range.start + (slice.len() - slice.trim_start_matches(is_wsp).len());
+    let end = range.end - (slice.len() - slice.trim_end_matches(is_wsp).len());
+    if start > end {
+        start..start
+    } else {
+        start..end
+    }
+}
+
+// the cookie-date algorithm of RFC 6265 §5.1.1, which accepts RFC 1123, RFC 850 and asctime
+// dates along with most of the broken variants servers send. the date is split into tokens
+// at delimiters, and the first token that looks like each of the time, day of month, month
+// and year is taken, in whatever order they appear. two-digit years are windowed into
+// 1970-2069, and dates which do not exist are rejected
+pub fn parse_cookie_date(s: &str) -> Option<Timestamp> {
+    let mut time = None;
+    let mut day_of_month = None;
+    let mut month = None;
+    let mut year = None;
+
+    for token in s.as_bytes().split(|&b| is_date_delimiter(b)).filter(|t| !t.is_empty()) {
+        if time.is_none() {
+            if let Some(hms) = parse_hms(token) {
+                time = Some(hms);
+                continue;
+            }
+        }
+        if day_of_month.is_none() {
+            if let Some((day, _)) = parse_digits(token, 1, 2) {
+                day_of_month = Some(day);
+                continue;
+            }
+        }
+        if month.is_none() && token.len() >= 3 {
+            if let Some(i) = MONTHS.iter()
+                .position(|m| m.as_bytes().eq_ignore_ascii_case(&token[..3])) {
+                month = Some(i as u32 + 1);
+                continue;
+            }
+        }
+        if year.is_none() {
+            if let Some((y, _)) = parse_digits(token, 2, 4) {
+                year = Some(y);
+            }
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let year = match year? {
+        y @ 70..=99 => y + 1900,
+        y @ 0..=69 => y + 2000,
+        y => y,
+    };
+    if year < 1601 {
+        return None;
+    }
+    Timestamp::from_utc(i64::from(year), month?, day_of_month?, hour, minute, second)
+}
+
+// delimiter = %x09 / %x20-2F / %x3B-40 / %x5B-60 / %x7B-7E
+#[inline]
+fn is_date_delimiter(b: u8) -> bool {
+    b == 0x09 || (0x20..=0x2f).contains(&b) || (0x3b..=0x40).contains(&b) ||
+    (0x5b..=0x60).contains(&b) || (0x7b..=0x7e).contains(&b)
+}
+
+// between `min` and `max` digits, followed by the rest of the token which must not begin
+// with a digit
+fn parse_digits(token: &[u8], min: usize, max: usize) -> Option<(u32, &[u8])> {
+    let n = token.iter().take_while(|b| b.is_ascii_digit()).count();
+    if n < min || n > max {
+        return None;
+    }
+    let value = token[..n].iter().fold(0, |v, &b| v * 10 + u32::from(b - b'0'));
+    Some((value, &token[n..]))
+}
+
+// hms-time = time-field ":" time-field ":" time-field, time-field = 1*2DIGIT
+fn parse_hms(token: &[u8]) -> Option<(u32, u32, u32)> {
+    let (hour, rest) = parse_digits(token, 1, 2)?;
+    let rest = rest.strip_prefix(b":")?;
+    let (minute, rest) = parse_digits(rest, 1, 2)?;
+    let rest = rest.strip_prefix(b":")?;
+    let (second, _) = parse_digits(rest, 1, 2)?;
+    Some((hour, minute, second))
+}
+
+fn parse_same_site(s: &str) -> Option<SameSite> {
+    if s.eq_ignore_ascii_case("Strict") {
+        Some(SameSite::Strict)
+    } else if s.eq_ignore_ascii_case("Lax") {
+        Some(SameSite::Lax)
+    } else if s.eq_ignore_ascii_case("None") {
+        Some(SameSite::None)
+    } else {
+        None
+    }
+}
+
+// unrecognized values leave the attribute unset, which a jar treats as Medium
+fn parse_priority(s: &str) -> Option<Priority> {
+    if s.eq_ignore_ascii_case("Low") {
+        Some(Priority::Low)
+    } else if s.eq_ignore_ascii_case("Medium") {
+        Some(Priority::Medium)
+    } else if s.eq_ignore_ascii_case("High") {
+        Some(Priority::High)
+    } else {
+        None
+    }
+}
+
+fn parse_max_age(s: &str) -> Option<i64> {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // the grammar is satisfied, so a failure here can only be overflow
+    Some(s.parse().unwrap_or(if s.starts_with('-') {
+        i64::MIN
+    } else {
+        i64::MAX
+    }))
+}
+
+#[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
+mod tests {
+    use super::{parse_cookie_date, ParseError};
+    use {Cookie, Priority, SameSite, Timestamp};
+
+    #[test]
+    fn name_value() {
+        let c = Cookie::parse("foo=bar").unwrap();
+        assert_eq!(c.cookie_pair(), ("foo", "bar"));
+        assert_eq!(c.as_str(), "foo=bar");
+
+        let c = Cookie::parse(" \tfoo = bar \t").unwrap();
+        assert_eq!(c.cookie_pair(), ("foo", "bar"));
+
+        let c = Cookie::parse("foo=").unwrap();
+        assert_eq!(c.cookie_pair(), ("foo", ""));
+
+        let c = Cookie::parse("foo=bar=baz").unwrap();
+        assert_eq!(c.cookie_pair(), ("foo", "bar=baz"));
+
+        assert_eq!(Cookie::parse("foo").err(), Some(ParseError::MissingPair));
+        assert_eq!(Cookie::parse("foo; bar=baz").err(), Some(ParseError::MissingPair));
+        assert_eq!(Cookie::parse("=bar").err(), Some(ParseError::EmptyName));
+        assert_eq!(Cookie::parse("  =bar").err(), Some(ParseError::EmptyName));
+    }
+
+    #[test]
+    fn attributes() {
+        let expires = "Thu, 22 Mar 2012 14:53:18 GMT";
+        let tm = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+
+        let c = Cookie::parse("foo=bar; expires=Thu, 22 Mar 2012 14:53:18 GMT; HTTPONLY; \
+                               max-age=60; secure; path=/foo; DOMAIN=.example.com")
+            .unwrap();
+        assert_eq!(c.name(), "foo");
+        assert_eq!(c.value(), "bar");
+        assert_eq!(c.domain(), Some("example.com"));
+        assert_eq!(c.path(), Some("/foo"));
+        assert_eq!(c.max_age(), Some(60));
+        assert_eq!(c.max_age_str(), Some("60"));
+        assert_eq!(c.secure(), true);
+        assert_eq!(c.httponly(), true);
+        assert_eq!(c.expires(), Some(tm));
+        assert_eq!(c.expires_str(), Some(expires));
+        assert_eq!(c.as_str(),
+                   "foo=bar; Domain=example.com; Path=/foo; Max-Age=60; Secure; HttpOnly; \
+                    Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+
+        let c = Cookie::parse("foo=bar; partitioned; Secure").unwrap();
+        assert_eq!(c.partitioned(), true);
+        assert_eq!(c.as_str(), "foo=bar; Secure; Partitioned");
+
+        // unrecognized attributes are kept, normalized like the others
+        let c = Cookie::parse("foo=bar;;Secure ;  ; Unknown = 1; Flag").unwrap();
+        assert_eq!(c.extensions().collect::<Vec<_>>(),
+                   vec![("Unknown", Some("1")), ("Flag", None)]);
+        assert_eq!(c.as_str(), "foo=bar; Unknown=1; Flag; Secure");
+
+        // recognized attributes with values which are ignored do not become extensions
+        let c = Cookie::parse("foo=bar; Max-Age=soon; Path=relative; SameSite=Sometimes")
+            .unwrap();
+        assert_eq!(c.as_str(), "foo=bar");
+    }
+
+    #[test]
+    fn last_wins() {
+        let c = Cookie::parse("foo=bar; Path=/a; Domain=a.com; Max-Age=10; Path=/b; \
+                               Domain=b.com; Max-Age=20")
+            .unwrap();
+        assert_eq!(c.as_str(), "foo=bar; Domain=b.com; Path=/b; Max-Age=20");
+
+        // an invalid Path falls back to the default-path, overriding earlier values
+        let c = Cookie::parse("foo=bar; Path=/a; Path=b").unwrap();
+        assert_eq!(c.path(), None);
+
+        // whereas invalid values for other attributes are ignored
+        let c = Cookie::parse("foo=bar; Domain=a.com; Domain=; Max-Age=10; Max-Age=1x; \
+                               Max-Age=; Expires=Thu, 22 Mar 2012 14:53:18 GMT; Expires=never")
+            .unwrap();
+        assert_eq!(c.as_str(),
+                   "foo=bar; Domain=a.com; Max-Age=10; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+    }
+
+    #[test]
+    fn same_site() {
+        let c = Cookie::parse("foo=bar; samesite=lax; Secure").unwrap();
+        assert_eq!(c.same_site(), Some(SameSite::Lax));
+        assert_eq!(c.as_str(), "foo=bar; Secure; SameSite=Lax");
+
+        let c = Cookie::parse("foo=bar; SameSite=Strict; SameSite=NONE").unwrap();
+        assert_eq!(c.same_site(), Some(SameSite::None));
+
+        let c = Cookie::parse("foo=bar; SameSite=Lax; SameSite=bogus").unwrap();
+        assert_eq!(c.same_site(), None);
+        assert_eq!(c.as_str(), "foo=bar");
+    }
+
+    #[test]
+    fn priority() {
+        let c = Cookie::parse("foo=bar; priority=HIGH; Expires=Thu, 22 Mar 2012 14:53:18 GMT; \
+                               SameSite=Lax")
+            .unwrap();
+        assert_eq!(c.priority(), Some(Priority::High));
+        assert_eq!(c.extensions().next(), None);
+        assert_eq!(c.as_str(),
+                   "foo=bar; SameSite=Lax; Priority=High; Expires=Thu, 22 Mar 2012 14:53:18 GMT");
+
+        let c = Cookie::parse("foo=bar; Priority=Low; Priority=urgent").unwrap();
+        assert_eq!(c.priority(), None);
+        assert_eq!(c.as_str(), "foo=bar");
+    }
+
+    #[test]
+    fn non_positive_max_age() {
+        let c = Cookie::parse("foo=bar; Max-Age=0").unwrap();
+        assert_eq!(c.max_age(), Some(0));
+        assert_eq!(c.as_str(), "foo=bar; Max-Age=0");
+
+        let c = Cookie::parse("foo=bar; Max-Age=-1").unwrap();
+        assert_eq!(c.max_age(), Some(-1));
+        assert_eq!(c.as_str(), "foo=bar; Max-Age=-1");
+
+        let c = Cookie::parse("foo=bar; Max-Age=99999999999999999999").unwrap();
+        assert_eq!(c.max_age(), Some(i64::MAX));
+
+        let c = Cookie::parse("foo=bar; Max-Age=-99999999999999999999").unwrap();
+        assert_eq!(c.max_age(), Some(i64::MIN));
+    }
+
+    #[test]
+    fn cookie_dates() {
+        let date = |y, mo, d, h, mi, s| Timestamp::from_utc(y, mo, d, h, mi, s);
+        let nov_6_1994 = date(1994, 11, 6, 8, 49, 37);
+        for s in &["Sun, 06 Nov 1994 08:49:37 GMT",
+                   "Sunday, 06-Nov-94 08:49:37 GMT",
+                   "Sun Nov  6 08:49:37 1994",
+                   "sun, 6 nov 1994 8:49:37 utc",
+                   "06 November 1994 08:49:37",
+                   "Sun Nov 06 1994 08:49:37 GMT+0000 (Coordinated Universal Time)",
+                   "1994-Nov-06 08:49:37.000Z",
+                   "08:49:37 Sun,06-Nov-1994"] {
+            assert_eq!(parse_cookie_date(s), nov_6_1994, "{}", s);
+        }
+
+        // two-digit years are windowed into 1970-2069
+        assert_eq!(parse_cookie_date("Thu, 01-Jan-70 00:00:00 GMT"), date(1970, 1, 1, 0, 0, 0));
+        assert_eq!(parse_cookie_date("Wed, 31-Dec-69 23:59:59 GMT"),
+                   date(2069, 12, 31, 23, 59, 59));
+        assert_eq!(parse_cookie_date("Sat, 01 Jan 0 00:00:00"), None);
+
+        for s in &["",
+                   "Sun, 06 Nov 1994",
+                   "06 Nov 1994 08:49",
+                   "Nov 1994 08:49:37",
+                   "06 1994 08:49:37",
+                   "Sat, 01 Jan 1600 00:00:00 GMT",
+                   "Tue, 32 Jan 2019 00:00:00 GMT",
+                   "Thu, 30 Feb 2012 00:00:00 GMT",
+                   "Thu, 22 Mar 2012 24:00:00 GMT",
+                   "Thu, 22 Mar 2012 23:60:00 GMT",
+                   "Thu, 22 Mar 2012 23:00:60 GMT",
+                   "Thu, 22 Mar 2012 123:00:00 GMT",
+                   "Thu, 022 Mar 2012 12:00:00 GMT",
+                   "Thu, 22 Mar 20123 12:00:00 GMT"] {
+            assert_eq!(parse_cookie_date(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn lenient_expires() {
+        let c = Cookie::parse("foo=bar; expires=Sunday, 06-Nov-94 08:49:37 GMT").unwrap();
+        assert_eq!(c.expires(), Timestamp::from_utc(1994, 11, 6, 8, 49, 37));
+        assert_eq!(c.expires_str(), Some("Sun, 06 Nov 1994 08:49:37 GMT"));
+
+        let c = Cookie::parse("foo=bar; Expires=Sun Nov  6 08:49:37 1994; Path=/").unwrap();
+        assert_eq!(c.as_str(), "foo=bar; Path=/; Expires=Sun, 06 Nov 1994 08:49:37 GMT");
+
+        let c = Cookie::parse("foo=bar; Expires=never").unwrap();
+        assert_eq!(c.expires(), None);
+        assert_eq!(c.as_str(), "foo=bar");
+    }
+}
@@ -0,0 +1,183 @@
+use std::error::Error;
+use std::fmt;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use getrandom;
+
+use key::Key;
+use Cookie;
+
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptError {
+    // the value is not base64url, or too short to hold a nonce and tag
+    InvalidEncoding,
+    // authentication failed under every accepted key
+    DecryptionFailed,
+    // the plaintext is not UTF-8, so was not produced by `encrypt`
+    InvalidPlaintext,
+}
+
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecryptError::InvalidEncoding => write!(f, "cookie value is not an encrypted value"),
+            DecryptError::DecryptionFailed => write!(f, "cookie value failed to decrypt"),
+            DecryptError::InvalidPlaintext => write!(f, "decrypted cookie value is not UTF-8"),
+        }
+    }
+}
+
+impl Error for DecryptError {}
+
+// encrypts cookie values with AES-256-GCM under a random nonce, storing
+// base64url(nonce || ciphertext || tag) as the value. the cookie name is authenticated as
+// associated data, so a value cannot be moved to another cookie. as with `SignedCookies`,
+// the primary key encrypts and any of the decryption keys are additionally accepted on read
+pub struct PrivateCookies {
+    key: Key,
+    decryption_keys: Vec<Key>,
+}
+
+impl PrivateCookies {
+    pub fn new(key: Key) -> PrivateCookies {
+        PrivateCookies {
+            key,
+            decryption_keys: Vec::new(),
+        }
+    }
+
+    pub fn with_decryption_keys<I>(mut self, keys: I) -> PrivateCookies
+        where I: IntoIterator<Item = Key>
+    {
+        self.decryption_keys.extend(keys);
+        self
+    }
+
+    pub fn encrypt(&self, cookie: &mut Cookie) {
+        let mut nonce = [0; NONCE_LEN];
+        getrandom::getrandom(&mut nonce).expect("failed to obtain randomness for nonce");
+        let payload = Payload {
+            msg: cookie.value().as_bytes(),
+            aad: cookie.name().as_bytes(),
+        };
+        let sealed = cipher(&self.key)
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .expect("cookie value too large to encrypt");
+
+        let mut data = Vec::with_capacity(NONCE_LEN + sealed.len());
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&sealed);
+        cookie.set_value(&URL_SAFE_NO_PAD.encode(data));
+    }
+
+    // the plaintext value, if it authenticates under one of the accepted keys
+    pub fn decrypt(&self, cookie: &Cookie) -> Result<String, DecryptError> {
+        let data = URL_SAFE_NO_PAD.decode(cookie.value())
+            .map_err(|_| DecryptError::InvalidEncoding)?;
+        if data.len() < NONCE_LEN + TAG_LEN {
+            return Err(DecryptError::InvalidEncoding);
+        }
+        let (nonce, sealed) = data.split_at(NONCE_LEN);
+
+        let plaintext = Some(&self.key)
+            .into_iter()
+            .chain(&self.decryption_keys)
+            .filter_map(|key| {
+                let payload = Payload {
+                    msg: sealed,
+                    aad: cookie.name().as_bytes(),
+                };
+                cipher(key).decrypt(Nonce::from_slice(nonce), payload).ok()
+            })
+            .next()
+            .ok_or(DecryptError::DecryptionFailed)?;
+        String::from_utf8(plaintext).map_err(|_| DecryptError::InvalidPlaintext)
+    }
+}
+
+fn cipher(key: &Key) -> Aes256Gcm {
+    Aes256Gcm::new_from_slice(key.as_bytes()).expect("key is the AES-256 key length")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DecryptError, PrivateCookies};
+    use key::Key;
+    use Cookie;
+
+    #[test]
+    fn round_trip() {
+        let private = PrivateCookies::new(Key::generate());
+        let mut c = Cookie::new("uid", "user-1234@example.com");
+        c.set_secure(true).set_httponly(true);
+        private.encrypt(&mut c);
+        assert!(!c.value().contains("user-1234"));
+        assert!(c.as_str().ends_with("; Secure; HttpOnly"));
+        assert!(c.validate().is_ok());
+        assert_eq!(private.decrypt(&c), Ok("user-1234@example.com".to_owned()));
+
+        // a fresh nonce is used every time
+        let mut again = Cookie::new("uid", "user-1234@example.com");
+        private.encrypt(&mut again);
+        assert!(c.value() != again.value());
+        assert_eq!(private.decrypt(&again), Ok("user-1234@example.com".to_owned()));
+
+        let mut c = Cookie::new("empty", "");
+        private.encrypt(&mut c);
+        assert_eq!(private.decrypt(&c), Ok(String::new()));
+    }
+
+    #[test]
+    fn tampering() {
+        let private = PrivateCookies::new(Key::from_bytes(&[3; 32]).unwrap());
+        let mut c = Cookie::new("uid", "1234");
+        private.encrypt(&mut c);
+        let sealed = c.value().to_owned();
+
+        // the ciphertext is bound to the cookie name
+        let other = Cookie::new("admin", &sealed);
+        assert_eq!(private.decrypt(&other), Err(DecryptError::DecryptionFailed));
+
+        // a character from the middle, as the last one also carries bits which base64url
+        // requires to be zero
+        let mut flipped = sealed.clone().into_bytes();
+        let middle = flipped.len() / 2;
+        flipped[middle] = if flipped[middle] == b'A' { b'B' } else { b'A' };
+        c.set_value(&String::from_utf8(flipped).unwrap());
+        assert_eq!(private.decrypt(&c), Err(DecryptError::DecryptionFailed));
+
+        c.set_value("plain");
+        assert_eq!(private.decrypt(&c), Err(DecryptError::InvalidEncoding));
+        c.set_value("not base64!");
+        assert_eq!(private.decrypt(&c), Err(DecryptError::InvalidEncoding));
+
+        c.set_value(&sealed);
+        let other_key = PrivateCookies::new(Key::from_bytes(&[4; 32]).unwrap());
+        assert_eq!(other_key.decrypt(&c), Err(DecryptError::DecryptionFailed));
+    }
+
+    #[test]
+    fn key_rotation() {
+        let old = Key::from_bytes(&[5; 32]).unwrap();
+        let new = Key::from_bytes(&[6; 32]).unwrap();
+
+        let mut c = Cookie::new("uid", "1234");
+        PrivateCookies::new(old.clone()).encrypt(&mut c);
+
+        let private = PrivateCookies::new(new.clone()).with_decryption_keys(vec![old]);
+        assert_eq!(private.decrypt(&c), Ok("1234".to_owned()));
+        assert_eq!(PrivateCookies::new(new.clone()).decrypt(&c),
+                   Err(DecryptError::DecryptionFailed));
+
+        // newly issued cookies use the primary key
+        let mut c = Cookie::new("uid", "1234");
+        private.encrypt(&mut c);
+        assert_eq!(PrivateCookies::new(new).decrypt(&c), Ok("1234".to_owned()));
+    }
+}
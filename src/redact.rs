@@ -0,0 +1,283 @@
+use std::fmt;
+
+use {Cookie, CookieJar};
+
+const MASK: &str = "<redacted>";
+
+// every value is masked; what `redacted()` uses when no policy is given
+static REDACT_ALL: RedactionPolicy = RedactionPolicy {
+    all: true,
+    names: Vec::new(),
+    patterns: Vec::new(),
+};
+
+// decides which cookies' values are masked when formatting through `Redacted`. cookie names
+// are compared case-insensitively against the listed names, and against the patterns, in
+// which `*` matches any run of characters, e.g. `*token*` or `__Host-*`
+#[derive(Debug, Clone, Default)]
+pub struct RedactionPolicy {
+    all: bool,
+    names: Vec<String>,
+    patterns: Vec<String>,
+}
+
+impl RedactionPolicy {
+    // a policy masking nothing, to which sensitive names are added
+    pub fn new() -> RedactionPolicy {
+        RedactionPolicy::default()
+    }
+
+    pub fn all() -> RedactionPolicy {
+        RedactionPolicy {
+            all: true,
+            ..RedactionPolicy::default()
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> RedactionPolicy {
+        self.names.push(name.to_owned());
+        self
+    }
+
+    pub fn with_pattern(mut self, pattern: &str) -> RedactionPolicy {
+        self.patterns.push(pattern.to_owned());
+        self
+    }
+
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        self.all || self.names.iter().any(|n| n.eq_ignore_ascii_case(name)) ||
+        self.patterns.iter().any(|p| glob_match(p.as_bytes(), name.as_bytes()))
+    }
+
+    // a request Cookie header with the values of sensitive cookies masked
+    pub fn redact_header<'a>(&'a self, header: &'a str) -> Redacted<'a, str> {
+        Redacted {
+            inner: header,
+            policy: self,
+        }
+    }
+}
+
+// formats the wrapped cookie, jar or Cookie header with sensitive values masked, keeping names
+// and attributes visible. Display gives what the wrapped value's own formatting would, and
+// Debug the same for a cookie, or a list of cookies for a jar
+pub struct Redacted<'a, T: ?Sized + 'a> {
+    inner: &'a T,
+    policy: &'a RedactionPolicy,
+}
+
+impl Cookie {
+    // formats with the value masked
+    pub fn redacted(&self) -> Redacted<'_, Cookie> {
+        self.redacted_with(&REDACT_ALL)
+    }
+
+    pub fn redacted_with<'a>(&'a self, policy: &'a RedactionPolicy) -> Redacted<'a, Cookie> {
+        Redacted {
+            inner: self,
+            policy,
+        }
+    }
+}
+
+impl CookieJar {
+    // formats with every value masked
+    pub fn redacted(&self) -> Redacted<'_, CookieJar> {
+        self.redacted_with(&REDACT_ALL)
+    }
+
+    pub fn redacted_with<'a>(&'a self, policy: &'a RedactionPolicy) -> Redacted<'a, CookieJar> {
+        Redacted {
+            inner: self,
+            policy,
+        }
+    }
+}
+
+impl<'a> fmt::Display for Redacted<'a, Cookie> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cookie = self.inner;
+        if !self.policy.is_sensitive(cookie.name()) {
+            return f.write_str(cookie.as_str());
+        }
+        write!(f, "{}={}{}", cookie.name(), MASK, &cookie.as_str()[cookie.value_end..])
+    }
+}
+
+impl<'a> fmt::Debug for Redacted<'a, Cookie> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cookie = self.inner;
+        if self.policy.is_sensitive(cookie.name()) {
+            cookie.fmt_debug(f, &MASK)
+        } else {
+            cookie.fmt_debug(f, &cookie.value())
+        }
+    }
+}
+
+// one Set-Cookie value per line
+impl<'a> fmt::Display for Redacted<'a, CookieJar> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, stored) in self.inner.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", stored.cookie().redacted_with(self.policy))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Debug for Redacted<'a, CookieJar> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.inner.iter().map(|c| c.cookie().redacted_with(self.policy)))
+            .finish()
+    }
+}
+
+// the header as given, with the value of each sensitive cookie-pair replaced. pairs are found
+// as `parse_cookie_header` finds them, but everything else, including whitespace, quotes and
+// segments which are not pairs, is copied through unchanged
+impl<'a> fmt::Display for Redacted<'a, str> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, segment) in self.inner.split(';').enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            match sensitive_value(segment, self.policy) {
+                Some((start, end)) => {
+                    write!(f, "{}{}{}", &segment[..start], MASK, &segment[end..])?;
+                }
+                None => f.write_str(segment)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Debug for Redacted<'a, str> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_string())
+    }
+}
+
+// the byte range of a segment's value, inside any DQUOTEs, if the segment is a cookie-pair
+// whose name is sensitive
+fn sensitive_value(segment: &str, policy: &RedactionPolicy) -> Option<(usize, usize)> {
+    let is_wsp = |c| c == ' ' || c == '\t';
+    let eq = segment.find('=')?;
+    let name = segment[..eq].trim_matches(is_wsp);
+    if name.is_empty() || !policy.is_sensitive(name) {
+        return None;
+    }
+    let start = segment.len() - segment[eq + 1..].trim_start_matches(is_wsp).len();
+    let end = start + segment[start..].trim_end_matches(is_wsp).len();
+    let value = &segment[start..end];
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        Some((start + 1, end - 1))
+    } else {
+        Some((start, end))
+    }
+}
+
+// case-insensitive matching where '*' in the pattern matches any run of bytes; backtracks only
+// to the most recent '*', which suffices for a single wildcard class
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p].eq_ignore_ascii_case(&name[n]) {
+            p += 1;
+            n += 1;
+        } else if let Some((sp, sn)) = star {
+            p = sp + 1;
+            n = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&b| b == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use url::Url;
+
+    use super::{glob_match, RedactionPolicy};
+    use {Cookie, CookieJar};
+
+    #[test]
+    fn patterns() {
+        assert!(glob_match(b"session", b"SESSION"));
+        assert!(!glob_match(b"session", b"session2"));
+        assert!(glob_match(b"*token*", b"csrf_token"));
+        assert!(glob_match(b"*token*", b"TokenV2"));
+        assert!(glob_match(b"__Host-*", b"__host-sid"));
+        assert!(!glob_match(b"__Host-*", b"sid"));
+        assert!(glob_match(b"a*b*c", b"aXbYbZc"));
+        assert!(!glob_match(b"a*b*c", b"aXbYbZ"));
+        assert!(glob_match(b"*", b""));
+
+        let policy = RedactionPolicy::new().with_name("sid").with_pattern("*token*");
+        assert!(policy.is_sensitive("SID"));
+        assert!(policy.is_sensitive("refresh_token"));
+        assert!(!policy.is_sensitive("theme"));
+        assert!(!RedactionPolicy::new().is_sensitive("sid"));
+        assert!(RedactionPolicy::all().is_sensitive("theme"));
+        // names are literal
+        assert!(!RedactionPolicy::new().with_name("s*").is_sensitive("sid"));
+        assert!(RedactionPolicy::new().with_name("s*").is_sensitive("S*"));
+    }
+
+    #[test]
+    fn cookies() {
+        let mut c = Cookie::new("sid", "s3cr3t");
+        c.set_path("/").set_secure(true).set_httponly(true);
+        assert_eq!(c.redacted().to_string(), "sid=<redacted>; Path=/; Secure; HttpOnly");
+        assert!(!format!("{:?}", c.redacted()).contains("s3cr3t"));
+        assert_eq!(format!("{:?}", c.redacted()),
+                   format!("{:?}", c).replace("\"s3cr3t\"", "\"<redacted>\""));
+
+        let policy = RedactionPolicy::new().with_name("sid");
+        assert_eq!(c.redacted_with(&policy).to_string(),
+                   "sid=<redacted>; Path=/; Secure; HttpOnly");
+        let theme = Cookie::new("theme", "dark");
+        assert_eq!(theme.redacted_with(&policy).to_string(), "theme=dark");
+        assert_eq!(format!("{:?}", theme.redacted_with(&policy)), format!("{:?}", theme));
+    }
+
+    #[test]
+    fn jars_and_headers() {
+        let mut jar = CookieJar::new();
+        let request = Url::parse("https://example.com/").unwrap();
+        jar.store(Cookie::parse("sid=s3cr3t; Path=/; Secure").unwrap(), &request).unwrap();
+        jar.store(Cookie::parse("theme=dark; Path=/").unwrap(), &request).unwrap();
+
+        let policy = RedactionPolicy::new().with_name("sid");
+        assert_eq!(jar.redacted_with(&policy).to_string(),
+                   "sid=<redacted>; Path=/; Secure\ntheme=dark; Path=/");
+        assert_eq!(jar.redacted().to_string(),
+                   "sid=<redacted>; Path=/; Secure\ntheme=<redacted>; Path=/");
+        let debug = format!("{:?}", jar.redacted_with(&policy));
+        assert!(debug.starts_with("[Cookie { name: \"sid\", value: \"<redacted>\""));
+        assert!(debug.contains("value: \"dark\""));
+
+        let header = jar.request_header(&request).unwrap();
+        assert_eq!(policy.redact_header(&header).to_string(), "sid=<redacted>; theme=dark");
+        assert_eq!(format!("{:?}", policy.redact_header(&header)),
+                   "\"sid=<redacted>; theme=dark\"");
+        assert_eq!(RedactionPolicy::all().redact_header(" a=1;;b=\"2\"").to_string(),
+                   " a=<redacted>;;b=\"<redacted>\"");
+        // only the values of sensitive pairs change
+        assert_eq!(policy.redact_header("theme=\"dark\" ;junk; =nameless;sid = \"s3cr3t\" ; \
+                                         SID=x=y;sid=")
+                       .to_string(),
+                   "theme=\"dark\" ;junk; =nameless;sid = \"<redacted>\" ; SID=<redacted>;\
+                    sid=<redacted>");
+    }
+}
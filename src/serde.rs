@@ -0,0 +1,344 @@
+// serde support. by default a `Cookie` is (de)serialized as its Set-Cookie string; the `map`
+// module gives a structured representation instead, selected per field with
+// `#[serde(with = "cooky::serde::map")]`
+
+use std::fmt;
+
+use serde_crate::de::{self, Deserialize, Deserializer, Visitor};
+use serde_crate::ser::{Serialize, Serializer};
+
+use {Cookie, Priority, SameSite};
+
+impl Serialize for Cookie {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        string::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cookie {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Cookie, D::Error> {
+        string::deserialize(deserializer)
+    }
+}
+
+impl Serialize for SameSite {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for SameSite {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SameSite, D::Error> {
+        deserializer.deserialize_str(NameVisitor(&[SameSite::Strict, SameSite::Lax, SameSite::None],
+                                                 SameSite::as_str))
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Priority, D::Error> {
+        deserializer.deserialize_str(NameVisitor(&[Priority::Low, Priority::Medium, Priority::High],
+                                                 Priority::as_str))
+    }
+}
+
+// one of `variants`, by its attribute value, matched case-insensitively as the parser does
+struct NameVisitor<T: 'static>(&'static [T], fn(&T) -> &'static str);
+
+impl<'de, T: Copy> Visitor<'de> for NameVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<_> = self.0.iter().map(self.1).collect();
+        write!(f, "one of {}", names.join(", "))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        self.0
+            .iter()
+            .find(|variant| (self.1)(variant).eq_ignore_ascii_case(v))
+            .cloned()
+            .ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+// the Set-Cookie string, e.g. `"sid=abc; Path=/; Secure"`, parsed leniently on the way back in
+pub mod string {
+    use std::fmt;
+
+    use serde_crate::de::{self, Deserializer, Visitor};
+    use serde_crate::ser::Serializer;
+
+    use Cookie;
+
+    pub fn serialize<S: Serializer>(cookie: &Cookie, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(cookie.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cookie, D::Error> {
+        deserializer.deserialize_str(CookieVisitor)
+    }
+
+    struct CookieVisitor;
+
+    impl<'de> Visitor<'de> for CookieVisitor {
+        type Value = Cookie;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a Set-Cookie header value")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Cookie, E> {
+            Cookie::parse(v).map_err(E::custom)
+        }
+    }
+}
+
+// a structure of the cookie's fields, e.g. in JSON
+// `{"name": "sid", "value": "abc", "path": "/", "secure": true}`. fields which are unset are
+// left out, and may be left out when deserializing. Expires is an IMF-fixdate string, read
+// back with `parse_cookie_date`; extensions are a list of `[name, value]` pairs with a null
+// value for those without one, and one which would not read back as the same extension is an
+// error
+pub mod map {
+    use serde_crate::de::{self, Deserialize, Deserializer};
+    use serde_crate::ser::{Serialize, Serializer};
+
+    use parse;
+    use validate;
+    use {parse_cookie_date, Cookie, CookieBuilder, Priority, SameSite};
+
+    #[derive(Serialize)]
+    #[serde(crate = "serde_crate")]
+    struct FieldsRef<'a> {
+        name: &'a str,
+        value: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        domain: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        path: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_age: Option<i64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expires: Option<&'a str>,
+        #[serde(skip_serializing_if = "is_false")]
+        secure: bool,
+        #[serde(skip_serializing_if = "is_false")]
+        httponly: bool,
+        #[serde(skip_serializing_if = "is_false")]
+        partitioned: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        same_site: Option<SameSite>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        priority: Option<Priority>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        extensions: Vec<(&'a str, Option<&'a str>)>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Fields {
+        name: String,
+        value: String,
+        #[serde(default)]
+        domain: Option<String>,
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        max_age: Option<i64>,
+        #[serde(default)]
+        expires: Option<String>,
+        #[serde(default)]
+        secure: bool,
+        #[serde(default)]
+        httponly: bool,
+        #[serde(default)]
+        partitioned: bool,
+        #[serde(default)]
+        same_site: Option<SameSite>,
+        #[serde(default)]
+        priority: Option<Priority>,
+        #[serde(default)]
+        extensions: Vec<(String, Option<String>)>,
+    }
+
+    fn is_false(b: &bool) -> bool {
+        !*b
+    }
+
+    pub fn serialize<S: Serializer>(cookie: &Cookie, serializer: S) -> Result<S::Ok, S::Error> {
+        FieldsRef {
+                name: cookie.name(),
+                value: cookie.value(),
+                domain: cookie.domain(),
+                path: cookie.path(),
+                max_age: cookie.max_age(),
+                expires: cookie.expires_str(),
+                secure: cookie.secure(),
+                httponly: cookie.httponly(),
+                partitioned: cookie.partitioned(),
+                same_site: cookie.same_site(),
+                priority: cookie.priority(),
+                extensions: cookie.extensions().collect(),
+            }
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Cookie, D::Error> {
+        let fields = Fields::deserialize(deserializer)?;
+        let expires = match fields.expires {
+            Some(ref date) => {
+                let expires = parse_cookie_date(date).ok_or_else(|| {
+                        de::Error::invalid_value(de::Unexpected::Str(date), &"a cookie-date")
+                    })?;
+                Some(expires)
+            }
+            None => None,
+        };
+        let mut builder = CookieBuilder::new(fields.name, fields.value)
+            .max_age(fields.max_age)
+            .secure(fields.secure)
+            .httponly(fields.httponly)
+            .partitioned(fields.partitioned)
+            .same_site(fields.same_site)
+            .priority(fields.priority)
+            .expires(expires);
+        if let Some(domain) = fields.domain {
+            builder = builder.domain(domain);
+        }
+        if let Some(path) = fields.path {
+            builder = builder.path(path);
+        }
+        let mut cookie = builder.build();
+        // an extension pushed as-is must not smuggle in an attribute of its own: a known name
+        // would be read back as that attribute, a ';' as another attribute after it
+        for (name, value) in &fields.extensions {
+            let value = value.as_ref().map(|v| &v[..]);
+            if parse::is_known_attribute(name) {
+                return Err(de::Error::invalid_value(de::Unexpected::Str(name),
+                                                    &"an extension attribute name"));
+            }
+            validate::check_extension(name, value).map_err(de::Error::custom)?;
+            cookie.push_extension(name, value);
+        }
+        Ok(cookie)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use {Cookie, Priority, SameSite};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    #[serde(crate = "serde_crate")]
+    struct Stored {
+        default: Cookie,
+        #[serde(with = "::serde::string")]
+        string: Cookie,
+        #[serde(with = "::serde::map")]
+        map: Cookie,
+    }
+
+    #[test]
+    fn string() {
+        let c = Cookie::parse("sid=abc; Path=/; Secure; Expires=Thu, 22 Mar 2012 14:53:18 GMT")
+            .unwrap();
+        let json = serde_json::to_string(&c).unwrap();
+        assert_eq!(json,
+                   "\"sid=abc; Path=/; Secure; Expires=Thu, 22 Mar 2012 14:53:18 GMT\"");
+        let back: Cookie = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.as_str(), c.as_str());
+
+        assert!(serde_json::from_str::<Cookie>("\"no pair\"").is_err());
+        assert!(serde_json::from_str::<Cookie>("42").is_err());
+    }
+
+    #[test]
+    fn map() {
+        let c = Cookie::parse("sid=abc; Domain=example.com; Path=/; Max-Age=60; SameParty; \
+                               X-Trace=1; Secure; HttpOnly; SameSite=Lax; Priority=High; \
+                               Expires=Thu, 22 Mar 2012 14:53:18 GMT")
+            .unwrap();
+        let stored = Stored {
+            default: Cookie::new("a", "1"),
+            string: Cookie::new("b", "2"),
+            map: c.clone(),
+        };
+        let json = serde_json::to_value(&stored).unwrap();
+        assert_eq!(json,
+                   json!({
+                       "default": "a=1",
+                       "string": "b=2",
+                       "map": {
+                           "name": "sid",
+                           "value": "abc",
+                           "domain": "example.com",
+                           "path": "/",
+                           "max_age": 60,
+                           "expires": "Thu, 22 Mar 2012 14:53:18 GMT",
+                           "secure": true,
+                           "httponly": true,
+                           "same_site": "Lax",
+                           "priority": "High",
+                           "extensions": [["SameParty", null], ["X-Trace", "1"]],
+                       },
+                   }));
+        let back: Stored = serde_json::from_value(json).unwrap();
+        assert_eq!(back, stored);
+        assert_eq!(back.map.as_str(), c.as_str());
+    }
+
+    #[test]
+    fn map_defaults() {
+        #[derive(Deserialize)]
+        #[serde(crate = "serde_crate")]
+        struct Config {
+            #[serde(with = "::serde::map")]
+            cookie: Cookie,
+        }
+
+        let config: Config = serde_json::from_str(r#"{"cookie": {"name": "theme", "value": "dark",
+                                                      "same_site": "strict",
+                                                      "priority": "LOW",
+                                                      "expires": "Sun, 06-Nov-94 08:49:37"}}"#)
+            .unwrap();
+        let c = config.cookie;
+        assert_eq!(c.same_site(), Some(SameSite::Strict));
+        assert_eq!(c.priority(), Some(Priority::Low));
+        assert_eq!(c.as_str(),
+                   "theme=dark; SameSite=Strict; Priority=Low; \
+                    Expires=Sun, 06 Nov 1994 08:49:37 GMT");
+
+        assert!(serde_json::from_str::<Config>(r#"{"cookie": {"name": "a"}}"#).is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"cookie": {"name": "a", "value": "b",
+                                                    "expires": "soon"}}"#)
+            .is_err());
+        assert!(serde_json::from_str::<Config>(r#"{"cookie": {"name": "a", "value": "b",
+                                                    "same_site": "sometimes"}}"#)
+            .is_err());
+
+        // extensions which would be read back as attributes of their own
+        let hostile = [r#"[["Domain", "evil.com"], ["X", "1;Secure"]]"#,
+                       r#"[["domain", null]]"#,
+                       r#"[["X", "1;Secure"]]"#,
+                       r#"[["X=Y", "z"]]"#,
+                       r#"[["X", "a\nb"]]"#,
+                       r#"[["", "1"]]"#];
+        for extensions in &hostile {
+            let json = format!(r#"{{"cookie": {{"name": "a", "value": "b",
+                                                "extensions": {}}}}}"#,
+                               extensions);
+            assert!(serde_json::from_str::<Config>(&json).is_err(), "{}", extensions);
+        }
+        let json = r#"{"cookie": {"name": "a", "value": "b", "extensions": [["X-Trace", "1"]]}}"#;
+        assert_eq!(serde_json::from_str::<Config>(json).unwrap().cookie.as_str(),
+                   "a=b; X-Trace=1");
+    }
+}
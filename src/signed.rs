@@ -0,0 +1,169 @@
+use std::error::Error;
+use std::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use key::Key;
+use Cookie;
+
+// the tag is appended to the value as ".<base64url HMAC-SHA256>"
+const TAG_SEPARATOR: char = '.';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    // the value carries no tag
+    MissingTag,
+    // the tag does not verify under any accepted key
+    InvalidTag,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SignatureError::MissingTag => write!(f, "cookie value is not signed"),
+            SignatureError::InvalidTag => write!(f, "cookie signature does not verify"),
+        }
+    }
+}
+
+impl Error for SignatureError {}
+
+// signs cookie values with HMAC-SHA256. the MAC covers the cookie name as well as the value,
+// so a signed value cannot be replayed under another cookie's name. cookies are always signed
+// with the primary key; verification keys are additionally accepted on read, to allow
+// rotating keys without invalidating cookies already issued
+pub struct SignedCookies {
+    key: Key,
+    verification_keys: Vec<Key>,
+}
+
+impl SignedCookies {
+    pub fn new(key: Key) -> SignedCookies {
+        SignedCookies {
+            key,
+            verification_keys: Vec::new(),
+        }
+    }
+
+    pub fn with_verification_keys<I>(mut self, keys: I) -> SignedCookies
+        where I: IntoIterator<Item = Key>
+    {
+        self.verification_keys.extend(keys);
+        self
+    }
+
+    pub fn sign(&self, cookie: &mut Cookie) {
+        let tag = URL_SAFE_NO_PAD.encode(mac(&self.key, cookie.name(), cookie.value())
+            .finalize()
+            .into_bytes());
+        let value = format!("{}{}{}", cookie.value(), TAG_SEPARATOR, tag);
+        cookie.set_value(&value);
+    }
+
+    // the value as it was before signing, if the tag verifies
+    pub fn verify<'a>(&self, cookie: &'a Cookie) -> Result<&'a str, SignatureError> {
+        let (value, tag) = cookie.value()
+            .rsplit_once(TAG_SEPARATOR)
+            .ok_or(SignatureError::MissingTag)?;
+        let tag = URL_SAFE_NO_PAD.decode(tag).map_err(|_| SignatureError::MissingTag)?;
+        let verified = Some(&self.key)
+            .into_iter()
+            .chain(&self.verification_keys)
+            .any(|key| mac(key, cookie.name(), value).verify_slice(&tag).is_ok());
+        if verified {
+            Ok(value)
+        } else {
+            Err(SignatureError::InvalidTag)
+        }
+    }
+}
+
+// the name is length-prefixed, so that the boundary between name and value is unambiguous: a
+// cookie named `a=b` with value `c` does not share a MAC with one named `a` with value `b=c`
+fn mac(key: &Key, name: &str, value: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(&(name.len() as u64).to_be_bytes());
+    mac.update(name.as_bytes());
+    mac.update(value.as_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SignatureError, SignedCookies};
+    use key::Key;
+    use Cookie;
+
+    #[test]
+    fn round_trip() {
+        let signer = SignedCookies::new(Key::generate());
+        let mut c = Cookie::new("session", "user.42");
+        c.set_path("/").set_httponly(true);
+        signer.sign(&mut c);
+        assert!(c.value().starts_with("user.42."));
+        assert_eq!(c.value().len(), "user.42.".len() + 43);
+        assert!(c.as_str().ends_with("; Path=/; HttpOnly"));
+        assert!(c.validate().is_ok());
+        assert_eq!(signer.verify(&c), Ok("user.42"));
+
+        let mut c = Cookie::new("empty", "");
+        signer.sign(&mut c);
+        assert_eq!(signer.verify(&c), Ok(""));
+    }
+
+    #[test]
+    fn tampering() {
+        let signer = SignedCookies::new(Key::from_bytes(&[7; 32]).unwrap());
+        let mut c = Cookie::new("session", "user=42");
+        signer.sign(&mut c);
+        let signed = c.value().to_owned();
+
+        c.set_value(&signed.replacen("42", "43", 1));
+        assert_eq!(signer.verify(&c), Err(SignatureError::InvalidTag));
+
+        // the same value is rejected under a different name
+        let mut other = Cookie::new("admin", &signed);
+        assert_eq!(signer.verify(&other), Err(SignatureError::InvalidTag));
+
+        other.set_value("user=42");
+        assert_eq!(signer.verify(&other), Err(SignatureError::MissingTag));
+        other.set_value("user=42.not*base64");
+        assert_eq!(signer.verify(&other), Err(SignatureError::MissingTag));
+
+        // nor moved across the name/value boundary
+        let mut split = Cookie::new("a=b", "c");
+        signer.sign(&mut split);
+        let reparsed = Cookie::parse(split.as_str()).unwrap();
+        assert_eq!(reparsed.cookie_pair().0, "a");
+        assert_eq!(signer.verify(&reparsed), Err(SignatureError::InvalidTag));
+        assert_eq!(signer.verify(&split), Ok("c"));
+
+        let other_signer = SignedCookies::new(Key::from_bytes(&[8; 32]).unwrap());
+        c.set_value(&signed);
+        assert_eq!(other_signer.verify(&c), Err(SignatureError::InvalidTag));
+    }
+
+    #[test]
+    fn key_rotation() {
+        let old = Key::from_bytes(&[1; 32]).unwrap();
+        let new = Key::from_bytes(&[2; 32]).unwrap();
+
+        let mut c = Cookie::new("session", "abc");
+        SignedCookies::new(old.clone()).sign(&mut c);
+
+        let signer = SignedCookies::new(new.clone()).with_verification_keys(vec![old]);
+        assert_eq!(signer.verify(&c), Ok("abc"));
+        assert_eq!(SignedCookies::new(new).verify(&c), Err(SignatureError::InvalidTag));
+
+        // newly issued cookies use the primary key
+        let mut c = Cookie::new("session", "abc");
+        signer.sign(&mut c);
+        let mut resigned = Cookie::new("session", "abc");
+        SignedCookies::new(Key::from_bytes(&[2; 32]).unwrap()).sign(&mut resigned);
+        assert_eq!(c.value(), resigned.value());
+    }
+}
@@ -0,0 +1,417 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "chrono")]
+use chrono;
+#[cfg(feature = "time")]
+use time;
+
+const SECS_PER_DAY: i64 = 86_400;
+// the range of four-digit years, 0000-01-01T00:00:00Z to 9999-12-31T23:59:59Z
+const MIN_FOUR_DIGIT_SECS: i64 = -62_167_219_200;
+const MAX_FOUR_DIGIT_SECS: i64 = 253_402_300_799;
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+pub(crate) const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug",
+                                       "Sep", "Oct", "Nov", "Dec"];
+
+// an instant in UTC, as the seconds and nanoseconds since the unix epoch. this is what
+// Expires holds, and what a jar keeps its creation, access and expiry times as; it converts
+// to and from `SystemTime`, and with the `time` and `chrono` features, to and from those
+// crates' date-time types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    pub const UNIX_EPOCH: Timestamp = Timestamp { secs: 0, nanos: 0 };
+
+    pub fn now() -> Timestamp {
+        Timestamp::from(SystemTime::now())
+    }
+
+    pub const fn from_unix(secs: i64) -> Timestamp {
+        Timestamp { secs, nanos: 0 }
+    }
+
+    // the given calendar date and time of day in UTC, if it exists and its seconds since the
+    // epoch fit in an i64
+    pub fn from_utc(year: i64,
+                    month: u32,
+                    day: u32,
+                    hour: u32,
+                    minute: u32,
+                    second: u32)
+                    -> Option<Timestamp> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) ||
+           hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        let days = days_from_civil(year, month, day)?;
+        let secs = days.checked_mul(SECS_PER_DAY)?
+            .checked_add(i64::from(hour * 3600 + minute * 60 + second))?;
+        Some(Timestamp::from_unix(secs))
+    }
+
+    pub fn unix_secs(&self) -> i64 {
+        self.secs
+    }
+
+    pub fn subsec_nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    pub fn saturating_add_secs(&self, secs: i64) -> Timestamp {
+        Timestamp {
+            secs: self.secs.saturating_add(secs),
+            nanos: self.nanos,
+        }
+    }
+
+    // an ISO 8601 date-time as HAR files and RFC 3339 write them, e.g.
+    // `2012-03-22T14:53:18.123Z` or `2012-03-22T16:53:18+02:00`. one without an offset is
+    // taken to be in UTC
+    pub fn parse_iso8601(s: &str) -> Option<Timestamp> {
+        let b = s.as_bytes();
+        if b.len() < 19 || !b[..19].is_ascii() || b[4] != b'-' || b[7] != b'-' ||
+           b[13] != b':' || b[16] != b':' || !(b[10] == b'T' || b[10] == b't' || b[10] == b' ') {
+            return None;
+        }
+        let year = digits(&s[0..4])?;
+        let t = Timestamp::from_utc(i64::from(year),
+                                    digits(&s[5..7])?,
+                                    digits(&s[8..10])?,
+                                    digits(&s[11..13])?,
+                                    digits(&s[14..16])?,
+                                    digits(&s[17..19])?)?;
+
+        let mut rest = &s[19..];
+        let mut nanos = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if len == 0 {
+                return None;
+            }
+            for (i, d) in fraction[..len].bytes().take(9).enumerate() {
+                nanos += u32::from(d - b'0') * 10u32.pow(8 - i as u32);
+            }
+            rest = &fraction[len..];
+        }
+
+        let offset = match rest {
+            "" | "Z" | "z" => 0,
+            _ => {
+                let sign = match rest.as_bytes()[0] {
+                    b'+' => 1,
+                    b'-' => -1,
+                    _ => return None,
+                };
+                if !rest.is_ascii() {
+                    return None;
+                }
+                let (hours, minutes) = match rest.len() {
+                    3 => (&rest[1..3], "00"),
+                    5 => (&rest[1..3], &rest[3..5]),
+                    6 if rest.as_bytes()[3] == b':' => (&rest[1..3], &rest[4..6]),
+                    _ => return None,
+                };
+                let (hours, minutes) = (digits(hours)?, digits(minutes)?);
+                if hours > 23 || minutes > 59 {
+                    return None;
+                }
+                sign * i64::from(hours * 3600 + minutes * 60)
+            }
+        };
+        Some(Timestamp {
+            secs: t.secs - offset,
+            nanos,
+        })
+    }
+
+    // the ISO 8601 date-time in UTC with milliseconds, e.g. `2012-03-22T14:53:18.123Z`. as
+    // `parse_iso8601` reads only four-digit years, an instant outside them is clamped to the
+    // nearest whole second within
+    pub fn to_iso8601(&self) -> String {
+        let t = if self.secs < MIN_FOUR_DIGIT_SECS {
+            Timestamp::from_unix(MIN_FOUR_DIGIT_SECS)
+        } else if self.secs > MAX_FOUR_DIGIT_SECS {
+            Timestamp::from_unix(MAX_FOUR_DIGIT_SECS)
+        } else {
+            *self
+        };
+        let (year, month, day, secs_of_day) = t.civil();
+        format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+                year,
+                month,
+                day,
+                secs_of_day / 3600,
+                secs_of_day / 60 % 60,
+                secs_of_day % 60,
+                t.nanos / 1_000_000)
+    }
+
+    // the calendar date, and the seconds into that day
+    fn civil(&self) -> (i64, u32, u32, i64) {
+        let (year, month, day) = civil_from_days(self.secs.div_euclid(SECS_PER_DAY));
+        (year, month, day, self.secs.rem_euclid(SECS_PER_DAY))
+    }
+}
+
+// a field of ASCII digits only, unlike `str::parse` which also takes a sign
+fn digits(s: &str) -> Option<u32> {
+    if s.bytes().all(|b| b.is_ascii_digit()) { s.parse().ok() } else { None }
+}
+
+// formats as the IMF-fixdate of RFC 7231 §7.1.1.1, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`;
+// sub-second precision is dropped
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day, secs_of_day) = self.civil();
+        // 1970-01-01 was a Thursday
+        let weekday = (self.secs.div_euclid(SECS_PER_DAY) + 4).rem_euclid(7) as usize;
+        write!(f,
+               "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+               WEEKDAYS[weekday],
+               day,
+               MONTHS[month as usize - 1],
+               year,
+               secs_of_day / 3600,
+               secs_of_day / 60 % 60,
+               secs_of_day % 60)
+    }
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(t: SystemTime) -> Timestamp {
+        match t.duration_since(UNIX_EPOCH) {
+            Ok(d) => {
+                Timestamp {
+                    secs: d.as_secs() as i64,
+                    nanos: d.subsec_nanos(),
+                }
+            }
+            Err(e) => {
+                let d = e.duration();
+                let secs = -(d.as_secs() as i64);
+                match d.subsec_nanos() {
+                    0 => Timestamp { secs, nanos: 0 },
+                    n => {
+                        Timestamp {
+                            secs: secs - 1,
+                            nanos: NANOS_PER_SEC - n,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl From<Timestamp> for SystemTime {
+    fn from(t: Timestamp) -> SystemTime {
+        if t.secs >= 0 {
+            UNIX_EPOCH + Duration::new(t.secs as u64, t.nanos)
+        } else {
+            UNIX_EPOCH - Duration::new(t.secs.unsigned_abs(), 0) + Duration::new(0, t.nanos)
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for Timestamp {
+    fn from(t: time::OffsetDateTime) -> Timestamp {
+        Timestamp {
+            secs: t.unix_timestamp(),
+            nanos: t.nanosecond(),
+        }
+    }
+}
+
+// saturates at the range `time` supports, which is narrower than a Timestamp's
+#[cfg(feature = "time")]
+impl From<Timestamp> for time::OffsetDateTime {
+    fn from(t: Timestamp) -> time::OffsetDateTime {
+        match time::OffsetDateTime::from_unix_timestamp(t.secs) {
+            Ok(dt) => dt.replace_nanosecond(t.nanos).unwrap_or(dt),
+            Err(_) if t.secs < 0 => time::PrimitiveDateTime::MIN.assume_utc(),
+            Err(_) => time::PrimitiveDateTime::MAX.assume_utc(),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> From<chrono::DateTime<Tz>> for Timestamp {
+    fn from(t: chrono::DateTime<Tz>) -> Timestamp {
+        Timestamp {
+            secs: t.timestamp(),
+            nanos: t.timestamp_subsec_nanos(),
+        }
+    }
+}
+
+// saturates at the range `chrono` supports
+#[cfg(feature = "chrono")]
+impl From<Timestamp> for chrono::DateTime<chrono::Utc> {
+    fn from(t: Timestamp) -> chrono::DateTime<chrono::Utc> {
+        match chrono::DateTime::from_timestamp(t.secs, t.nanos) {
+            Some(dt) => dt,
+            None if t.secs < 0 => chrono::DateTime::<chrono::Utc>::MIN_UTC,
+            None => chrono::DateTime::<chrono::Utc>::MAX_UTC,
+        }
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// the days since 1970-01-01 of a proleptic Gregorian date, after Howard Hinnant's
+// `days_from_civil`, or None where that overflows
+fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    let year = if month <= 2 { year.checked_sub(1)? } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 +
+                      i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era.checked_mul(146_097)?.checked_add(day_of_era - 719_468)
+}
+
+// the inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 -
+                       day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::Timestamp;
+
+    #[test]
+    fn calendar() {
+        let t = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        assert_eq!(t.unix_secs(), 1_332_427_998);
+        assert_eq!(t.to_string(), "Thu, 22 Mar 2012 14:53:18 GMT");
+        assert_eq!(Timestamp::UNIX_EPOCH.to_string(), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(Timestamp::from_utc(1900, 1, 1, 0, 0, 0).unwrap().to_string(),
+                   "Mon, 01 Jan 1900 00:00:00 GMT");
+        assert_eq!(Timestamp::from_utc(2000, 2, 29, 23, 59, 59).unwrap().to_string(),
+                   "Tue, 29 Feb 2000 23:59:59 GMT");
+        assert_eq!(Timestamp::from_utc(9999, 12, 31, 0, 0, 0).unwrap().to_string(),
+                   "Fri, 31 Dec 9999 00:00:00 GMT");
+
+        assert_eq!(Timestamp::from_utc(1900, 2, 29, 0, 0, 0), None);
+        assert_eq!(Timestamp::from_utc(2012, 13, 1, 0, 0, 0), None);
+        assert_eq!(Timestamp::from_utc(2012, 4, 31, 0, 0, 0), None);
+        assert_eq!(Timestamp::from_utc(2012, 1, 1, 24, 0, 0), None);
+        assert_eq!(Timestamp::from_utc(i64::MAX, 1, 1, 0, 0, 0), None);
+        assert_eq!(Timestamp::from_utc(i64::MIN, 1, 1, 0, 0, 0), None);
+        assert_eq!(Timestamp::from_utc(i64::MAX / 365, 12, 31, 23, 59, 59), None);
+    }
+
+    #[test]
+    fn system_time() {
+        let now = SystemTime::now();
+        assert_eq!(SystemTime::from(Timestamp::from(now)), now);
+
+        let before = UNIX_EPOCH - Duration::new(1, 250_000_000);
+        let t = Timestamp::from(before);
+        assert_eq!((t.unix_secs(), t.subsec_nanos()), (-2, 750_000_000));
+        assert_eq!(SystemTime::from(t), before);
+        assert_eq!(t.to_string(), "Wed, 31 Dec 1969 23:59:58 GMT");
+
+        assert!(Timestamp::from_unix(-1) < Timestamp::UNIX_EPOCH);
+        assert_eq!(Timestamp::from_unix(i64::MAX - 1).saturating_add_secs(10).unix_secs(),
+                   i64::MAX);
+    }
+
+    #[test]
+    fn iso8601() {
+        let t = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let parse = Timestamp::parse_iso8601;
+        assert_eq!(parse("2012-03-22T14:53:18Z"), Some(t));
+        assert_eq!(parse("2012-03-22t14:53:18"), Some(t));
+        assert_eq!(parse("2012-03-22 16:53:18+02:00"), Some(t));
+        assert_eq!(parse("2012-03-22T09:23:18-0530"), Some(t));
+        assert_eq!(parse("2012-03-22T15:53:18+01"), Some(t));
+        let millis = parse("2012-03-22T14:53:18.123Z").unwrap();
+        assert_eq!((millis.unix_secs(), millis.subsec_nanos()), (t.unix_secs(), 123_000_000));
+        assert_eq!(parse("2012-03-22T14:53:18.1234567891Z").unwrap().subsec_nanos(),
+                   123_456_789);
+
+        assert_eq!(parse("2012-03-22"), None);
+        assert_eq!(parse("2012-02-30T14:53:18Z"), None);
+        assert_eq!(parse("2012-03-22T14:53:18.Z"), None);
+        assert_eq!(parse("2012-03-22T14:53:18+2:00"), None);
+        assert_eq!(parse("2012-03-22T14:53:18 GMT"), None);
+        assert_eq!(parse("+012-03-22T14:53:18Z"), None);
+        assert_eq!(parse("2012-03-2\u{e9}14:53:18Z"), None);
+        assert_eq!(parse("2012-03-22T14:53:18+\u{e9}00"), None);
+
+        assert_eq!(millis.to_iso8601(), "2012-03-22T14:53:18.123Z");
+        assert_eq!(Timestamp::from_unix(-1).to_iso8601(), "1969-12-31T23:59:59.000Z");
+        assert_eq!(parse(&millis.to_iso8601()), Some(millis));
+
+        // clamped to the four-digit years the parser reads back
+        let last = parse("9999-12-31T23:59:59Z").unwrap();
+        assert_eq!(parse("9999-12-31T23:59:59-23:59").unwrap().to_iso8601(),
+                   "9999-12-31T23:59:59.000Z");
+        assert_eq!(Timestamp::from_unix(i64::MAX).to_iso8601(), "9999-12-31T23:59:59.000Z");
+        assert_eq!(parse(&Timestamp::from_unix(i64::MAX).to_iso8601()), Some(last));
+        assert_eq!(Timestamp::from_unix(i64::MIN).to_iso8601(), "0000-01-01T00:00:00.000Z");
+        assert_eq!(parse("0000-01-01T00:00:00Z"),
+                   Some(Timestamp::from_unix(super::MIN_FOUR_DIGIT_SECS)));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_conversions() {
+        use time::{Duration, OffsetDateTime, UtcOffset};
+
+        let t = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let dt = OffsetDateTime::from(t);
+        assert_eq!(dt.unix_timestamp(), 1_332_427_998);
+        let offset = dt.to_offset(UtcOffset::from_hms(-5, 0, 0).unwrap());
+        assert_eq!(Timestamp::from(offset), t);
+        assert_eq!(OffsetDateTime::from(Timestamp::from_unix(i64::MAX)).year(), 9999);
+        assert_eq!(Timestamp::from(dt + Duration::seconds(1)), t.saturating_add_secs(1));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_conversions() {
+        use chrono::{DateTime, FixedOffset, Utc};
+
+        let t = Timestamp::from_utc(2012, 3, 22, 14, 53, 18).unwrap();
+        let dt = DateTime::<Utc>::from(t);
+        assert_eq!(dt.timestamp(), 1_332_427_998);
+        let offset = dt.with_timezone(&FixedOffset::east_opt(9 * 3600).unwrap());
+        assert_eq!(Timestamp::from(offset), t);
+        assert_eq!(DateTime::<Utc>::from(Timestamp::from_unix(i64::MIN)),
+                   DateTime::<Utc>::MIN_UTC);
+    }
+}
@@ -0,0 +1,320 @@
+use std::error::Error;
+use std::fmt;
+
+use {Cookie, SameSite};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    // the name is not an RFC 2616 token
+    InvalidName,
+    // the value is not a sequence of cookie-octets, optionally DQUOTE-wrapped
+    InvalidValue,
+    // the Domain is not a valid domain name
+    InvalidDomain,
+    // the Path contains a control character or ';'
+    InvalidPath,
+    // an extension attribute has an empty name, or contains a control character or ';'
+    InvalidExtension,
+    // SameSite=None was set without Secure, which user agents reject
+    SameSiteNoneInsecure,
+    // Partitioned was set without Secure, which user agents reject
+    PartitionedInsecure,
+    // a `__Secure-` prefixed name without Secure
+    SecurePrefixInsecure,
+    // a `__Host-` prefixed name without Secure
+    HostPrefixInsecure,
+    // a `__Host-` prefixed name with a Domain
+    HostPrefixDomain,
+    // a `__Host-` prefixed name without Path=/
+    HostPrefixPath,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::InvalidName => write!(f, "cookie name is not a valid token"),
+            ValidationError::InvalidValue => {
+                write!(f, "cookie value contains characters outside cookie-octet")
+            }
+            ValidationError::InvalidDomain => write!(f, "cookie Domain is not a valid domain"),
+            ValidationError::InvalidPath => write!(f, "cookie Path contains invalid characters"),
+            ValidationError::InvalidExtension => {
+                write!(f, "cookie extension attribute contains invalid characters")
+            }
+            ValidationError::SameSiteNoneInsecure => {
+                write!(f, "cookie has SameSite=None but is not Secure")
+            }
+            ValidationError::PartitionedInsecure => {
+                write!(f, "cookie is Partitioned but is not Secure")
+            }
+            ValidationError::SecurePrefixInsecure => {
+                write!(f, "cookie name has the __Secure- prefix but is not Secure")
+            }
+            ValidationError::HostPrefixInsecure => {
+                write!(f, "cookie name has the __Host- prefix but is not Secure")
+            }
+            ValidationError::HostPrefixDomain => {
+                write!(f, "cookie name has the __Host- prefix but sets a Domain")
+            }
+            ValidationError::HostPrefixPath => {
+                write!(f, "cookie name has the __Host- prefix but its Path is not /")
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+pub fn validate(cookie: &Cookie) -> Result<(), ValidationError> {
+    check_name(cookie.name())?;
+    check_value(cookie.value())?;
+    if let Some(domain) = cookie.domain() {
+        check_domain(domain)?;
+    }
+    if let Some(path) = cookie.path() {
+        check_path(path)?;
+    }
+    for (name, value) in cookie.extensions() {
+        check_extension(name, value)?;
+    }
+    if cookie.same_site() == Some(SameSite::None) && !cookie.secure() {
+        return Err(ValidationError::SameSiteNoneInsecure);
+    }
+    if cookie.partitioned() && !cookie.secure() {
+        return Err(ValidationError::PartitionedInsecure);
+    }
+    check_prefix(cookie)
+}
+
+const SECURE_PREFIX: &str = "__Secure-";
+const HOST_PREFIX: &str = "__Host-";
+
+pub fn has_name_prefix(name: &str) -> bool {
+    has_prefix(name, SECURE_PREFIX) || has_prefix(name, HOST_PREFIX)
+}
+
+#[inline]
+fn has_prefix(name: &str, prefix: &str) -> bool {
+    name.len() >= prefix.len() &&
+    name.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix.as_bytes())
+}
+
+// the cookie name prefixes of RFC 6265bis §4.1.3, matched case-insensitively as user
+// agents do
+pub fn check_prefix(cookie: &Cookie) -> Result<(), ValidationError> {
+    let name = cookie.name();
+    if has_prefix(name, SECURE_PREFIX) && !cookie.secure() {
+        return Err(ValidationError::SecurePrefixInsecure);
+    }
+    if has_prefix(name, HOST_PREFIX) {
+        if !cookie.secure() {
+            return Err(ValidationError::HostPrefixInsecure);
+        }
+        if cookie.domain().is_some() {
+            return Err(ValidationError::HostPrefixDomain);
+        }
+        if cookie.path() != Some("/") {
+            return Err(ValidationError::HostPrefixPath);
+        }
+    }
+    Ok(())
+}
+
+// token = 1*<any CHAR except CTLs or separators> (RFC 2616 §2.2)
+pub fn check_name(name: &str) -> Result<(), ValidationError> {
+    let is_token_char = |b: u8| {
+        (0x21..0x7f).contains(&b) &&
+        !matches!(b,
+                  b'(' | b')' | b'<' | b'>' | b'@' | b',' | b';' | b':' | b'\\' | b'"' |
+                  b'/' | b'[' | b']' | b'?' | b'=' | b'{' | b'}')
+    };
+    if name.is_empty() || !name.bytes().all(is_token_char) {
+        return Err(ValidationError::InvalidName);
+    }
+    Ok(())
+}
+
+// cookie-value = *cookie-octet / ( DQUOTE *cookie-octet DQUOTE ) (RFC 6265 §4.1.1)
+pub fn check_value(value: &str) -> Result<(), ValidationError> {
+    let is_cookie_octet = |b: u8| {
+        b == 0x21 || (0x23..=0x2b).contains(&b) || (0x2d..=0x3a).contains(&b) ||
+        (0x3c..=0x5b).contains(&b) || (0x5d..=0x7e).contains(&b)
+    };
+    let octets = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    };
+    if !octets.bytes().all(is_cookie_octet) {
+        return Err(ValidationError::InvalidValue);
+    }
+    Ok(())
+}
+
+// whether a name and value read from another store, which need not be tokens and
+// cookie-octets, still serialize as a cookie-pair that parses back the same: a non-empty name
+// without '=' or ';', and a value without ';'
+pub fn is_storable_pair(name: &str, value: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', ';']) && !value.contains(';')
+}
+
+// a domain name of letter-digit-hyphen labels (RFC 1034 §3.5, as referenced by RFC 6265
+// §4.1.1); the leading '.' which user agents ignore is tolerated
+pub fn check_domain(domain: &str) -> Result<(), ValidationError> {
+    let domain = domain.strip_prefix('.').unwrap_or(domain);
+    let is_label = |label: &str| {
+        !label.is_empty() && !label.starts_with('-') && !label.ends_with('-') &&
+        label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+    };
+    if !domain.split('.').all(is_label) {
+        return Err(ValidationError::InvalidDomain);
+    }
+    Ok(())
+}
+
+// path-value = <any CHAR except CTLs or ";"> (RFC 6265 §4.1.1)
+pub fn check_path(path: &str) -> Result<(), ValidationError> {
+    if !path.bytes().all(|b| (0x20..0x7f).contains(&b) && b != b';') {
+        return Err(ValidationError::InvalidPath);
+    }
+    Ok(())
+}
+
+// extension-av = <any CHAR except CTLs or ";"> (RFC 6265 §4.1.1); the name additionally
+// cannot contain '=', or it would not parse back the same
+pub fn check_extension(name: &str, value: Option<&str>) -> Result<(), ValidationError> {
+    let is_av_octet = |b: u8| (0x20..0x7f).contains(&b) && b != b';';
+    if name.is_empty() || name.contains('=') || !name.bytes().all(is_av_octet) ||
+       !value.unwrap_or("").bytes().all(is_av_octet) {
+        return Err(ValidationError::InvalidExtension);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_domain, check_extension, check_name, check_path, check_value,
+                ValidationError};
+    use {Cookie, SameSite};
+
+    #[test]
+    fn same_site_none() {
+        let mut c = Cookie::new("foo", "bar");
+        assert_eq!(c.validate(), Ok(()));
+        c.set_same_site(Some(SameSite::None));
+        assert_eq!(c.validate(), Err(ValidationError::SameSiteNoneInsecure));
+        c.set_secure(true);
+        assert_eq!(c.validate(), Ok(()));
+        c.set_secure(false).set_same_site(Some(SameSite::Lax));
+        assert_eq!(c.validate(), Ok(()));
+    }
+
+    #[test]
+    fn partitioned() {
+        let mut c = Cookie::new("foo", "bar");
+        c.set_partitioned(true);
+        assert_eq!(c.validate(), Err(ValidationError::PartitionedInsecure));
+        c.set_secure(true);
+        assert_eq!(c.validate(), Ok(()));
+    }
+
+    #[test]
+    fn prefixes() {
+        let mut c = Cookie::new("__Secure-id", "1");
+        assert_eq!(c.validate(), Err(ValidationError::SecurePrefixInsecure));
+        assert_eq!(c.as_checked_str(), Err(ValidationError::SecurePrefixInsecure));
+        c.set_secure(true).set_domain("example.com").set_path("/a");
+        assert_eq!(c.as_checked_str(),
+                   Ok("__Secure-id=1; Domain=example.com; Path=/a; Secure"));
+
+        let mut c = Cookie::new("__host-id", "1");
+        assert_eq!(c.validate(), Err(ValidationError::HostPrefixInsecure));
+        c.set_secure(true);
+        assert_eq!(c.validate(), Err(ValidationError::HostPrefixPath));
+        c.set_path("/a");
+        assert_eq!(c.validate(), Err(ValidationError::HostPrefixPath));
+        c.set_path("/").set_domain("example.com");
+        assert_eq!(c.validate(), Err(ValidationError::HostPrefixDomain));
+        c.set_domain("");
+        assert_eq!(c.as_checked_str(), Ok("__host-id=1; Path=/; Secure"));
+
+        assert_eq!(Cookie::new("__Hostile", "1").validate(), Ok(()));
+        assert_eq!(Cookie::new("_Secure-id", "1").validate(), Ok(()));
+    }
+
+    #[test]
+    fn grammar() {
+        assert!(check_name("foo").is_ok());
+        assert!(check_name("__Host-id.v2").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name("a=b").is_err());
+        assert!(check_name("a;b").is_err());
+        assert!(check_name("a b").is_err());
+        assert!(check_name("a\u{7f}").is_err());
+        assert!(check_name("caf\u{e9}").is_err());
+
+        assert!(check_value("").is_ok());
+        assert!(check_value("abc123!#$%&'()*+-./:<=>?@[]^_`{|}~").is_ok());
+        assert!(check_value("\"quoted\"").is_ok());
+        assert!(check_value("\"\"").is_ok());
+        assert!(check_value("\"").is_err());
+        assert!(check_value("a b").is_err());
+        assert!(check_value("a,b").is_err());
+        assert!(check_value("a;b").is_err());
+        assert!(check_value("a\\b").is_err());
+        assert!(check_value("\"a\"b\"").is_err());
+        assert!(check_value("\u{2603}").is_err());
+
+        assert!(check_domain("example.com").is_ok());
+        assert!(check_domain(".www-1.example.com").is_ok());
+        assert!(check_domain("127.0.0.1").is_ok());
+        assert!(check_domain("").is_err());
+        assert!(check_domain("example..com").is_err());
+        assert!(check_domain("-example.com").is_err());
+        assert!(check_domain("exa_mple.com").is_err());
+        assert!(check_domain("example.com;").is_err());
+
+        assert!(check_path("/").is_ok());
+        assert!(check_path("/a b/c,d").is_ok());
+        assert!(check_path("/a;b").is_err());
+        assert!(check_path("/a\nb").is_err());
+
+        assert!(check_extension("SameParty", None).is_ok());
+        assert!(check_extension("X-Tier", Some("High")).is_ok());
+        assert!(check_extension("X", Some("a=b c")).is_ok());
+        assert!(check_extension("", Some("a")).is_err());
+        assert!(check_extension("a=b", None).is_err());
+        assert!(check_extension("X", Some("a;b")).is_err());
+        assert!(check_extension("X", Some("a\tb")).is_err());
+    }
+
+    #[test]
+    fn fallible_setters() {
+        let mut c = Cookie::try_new(" foo ", "bar").unwrap();
+        assert_eq!(c.as_str(), "foo=bar");
+        assert_eq!(Cookie::try_new("fo;o", "bar").err(), Some(ValidationError::InvalidName));
+        assert_eq!(Cookie::try_new("foo", "b;ar").err(), Some(ValidationError::InvalidValue));
+
+        assert_eq!(c.try_set_name("a=b").err(), Some(ValidationError::InvalidName));
+        assert_eq!(c.try_set_value("a b").err(), Some(ValidationError::InvalidValue));
+        assert_eq!(c.try_set_domain("a;b").err(), Some(ValidationError::InvalidDomain));
+        assert_eq!(c.try_set_path("/;").err(), Some(ValidationError::InvalidPath));
+        assert_eq!(c.as_str(), "foo=bar");
+
+        c.try_set_name("baz").unwrap().try_set_value("\"qux\"").unwrap();
+        c.try_set_domain("example.com").unwrap().try_set_path("/").unwrap();
+        assert_eq!(c.as_str(), "baz=\"qux\"; Domain=example.com; Path=/");
+        c.try_set_domain("").unwrap().try_set_path("").unwrap();
+        assert_eq!(c.as_str(), "baz=\"qux\"");
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(Cookie::new("a b", "c").validate(), Err(ValidationError::InvalidName));
+        assert_eq!(Cookie::new("a", "c,d").validate(), Err(ValidationError::InvalidValue));
+        assert_eq!(Cookie::new("a", "b").set_domain("a..b").validate(),
+                   Err(ValidationError::InvalidDomain));
+        assert_eq!(Cookie::parse("a=b; Domain=.example.com; Path=/x").unwrap().validate(),
+                   Ok(()));
+    }
+}